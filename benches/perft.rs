@@ -0,0 +1,103 @@
+// Times perft and counts the heap allocations it makes, run with `cargo bench`. Comparing perft(4) from the
+// starting position across commits shows what changes to move generation (like the occupancy bitboards) gain.
+// Also compares replaying a game with make_move against make_move_trusted.
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+use viering_chess::{Game, Move};
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+// Each measurement keeps the fastest of a few runs, to smooth out noise from the rest of the machine
+const RUNS: usize = 5;
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+// Runs f a few times, returning its result, the fastest time and the allocations of a single run
+fn measure<T>(mut f: impl FnMut() -> T) -> (T, Duration, usize) {
+    let mut fastest = Duration::MAX;
+    let mut result = None;
+    let mut allocations = 0;
+    for _ in 0..RUNS {
+        let allocations_before = ALLOCATIONS.load(Ordering::Relaxed);
+        let start = Instant::now();
+        let value = f();
+        fastest = fastest.min(start.elapsed());
+        allocations = ALLOCATIONS.load(Ordering::Relaxed) - allocations_before;
+        result = Some(value);
+    }
+    (result.unwrap(), fastest, allocations)
+}
+
+// The opera game, replayed this many times per measurement
+const OPERA_GAME: &str = "1. e4 e5 2. Nf3 d6 3. d4 Bg4 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6 7. Qb3 Qe7 8. Nc3 c6 \
+    9. Bg5 b5 10. Nxb5 cxb5 11. Bxb5+ Nbd7 12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ Nxd7 16. Qb8+ Nxb8 17. Rd8#";
+const REPLAYS: usize = 200;
+
+fn replay(moves: &[Move], mut make_move: impl FnMut(&mut Game, &Move)) -> Game {
+    let mut game = Game::new();
+    for _ in 0..REPLAYS {
+        game = Game::new();
+        for made_move in moves {
+            make_move(&mut game, made_move);
+        }
+    }
+    game
+}
+
+fn main() {
+    let positions = [
+        ("starting position", "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", 4),
+        ("kiwipete", "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1", 3),
+    ];
+
+    for (name, fen, depth) in positions {
+        let game = Game::from_fen(fen).unwrap();
+        let (nodes, elapsed, allocations) = measure(|| game.perft(depth));
+        println!(
+            "{}: perft({}) = {} nodes in {:?}, {} allocations ({:.2} per node)",
+            name,
+            depth,
+            nodes,
+            elapsed,
+            allocations,
+            allocations as f64 / nodes as f64
+        );
+    }
+
+    let mut game = Game::new();
+    game.load_pgn(OPERA_GAME).unwrap();
+    let moves = game.move_history().to_vec();
+    for (name, make_move) in [
+        ("make_move", (|game: &mut Game, made_move: &Move| {
+            game.make_move(made_move.from, made_move.to).unwrap();
+        }) as fn(&mut Game, &Move)),
+        ("make_move_trusted", |game: &mut Game, made_move: &Move| game.make_move_trusted(made_move)),
+    ] {
+        let (replayed, elapsed, allocations) = measure(|| replay(&moves, make_move));
+        assert_eq!(replayed.to_fen(), game.to_fen());
+        println!(
+            "replaying the opera game {} times with {}: {} plies in {:?}, {} allocations",
+            REPLAYS,
+            name,
+            REPLAYS * moves.len(),
+            elapsed,
+            allocations
+        );
+    }
+}
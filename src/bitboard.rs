@@ -0,0 +1,102 @@
+use crate::{Color, Piece, Position, Square};
+
+// The eight sliding directions, as (x, y) steps like PositionBuilder::walk takes
+const DIRECTIONS: [(i32, i32); 8] = [(0, 1), (1, 1), (1, 0), (1, -1), (0, -1), (-1, -1), (-1, 0), (-1, 1)];
+
+// Squares reached from every square in each direction on an empty board, with bits indexed like Position::to_index
+const RAYS: [[u64; 8 * 8]; 8] = generate_rays();
+
+const fn generate_rays() -> [[u64; 8 * 8]; 8] {
+    let mut rays = [[0; 8 * 8]; 8];
+    let mut direction = 0;
+    while direction < 8 {
+        let (dx, dy) = DIRECTIONS[direction];
+        let mut i = 0;
+        while i < 8 * 8 {
+            let mut x = (i % 8) as i32 + dx;
+            let mut y = 7 - (i / 8) as i32 + dy;
+            while x >= 0 && x <= 7 && y >= 0 && y <= 7 {
+                rays[direction][i] |= 1 << ((7 - y) * 8 + x);
+                x += dx;
+                y += dy;
+            }
+            i += 1;
+        }
+        direction += 1;
+    }
+    rays
+}
+
+fn bit(pos: Position) -> u64 {
+    1 << pos.to_index()
+}
+
+fn ray(from: Position, direction: (i32, i32)) -> u64 {
+    let direction = DIRECTIONS
+        .iter()
+        .position(|d| *d == direction)
+        .expect("ray direction should be one of the eight sliding directions");
+    RAYS[direction][from.to_index()]
+}
+
+// Which squares are occupied, kept up to date by Game::set_square. The squares array stays the source of truth
+// for what the pieces are, this only speeds up looking for them.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct Occupancy {
+    pub(crate) all: u64,
+    pub(crate) white: u64,
+    pub(crate) black: u64,
+}
+
+impl Occupancy {
+    pub(crate) fn from_squares(squares: &[Square; 8 * 8]) -> Self {
+        let mut occupancy = Self::default();
+        for (i, square) in squares.iter().enumerate() {
+            if let Some(piece) = square {
+                occupancy.toggle(*piece, Position::from_index(i as u8).unwrap());
+            }
+        }
+        occupancy
+    }
+
+    // Adds the piece if its square is free in the bitboards, removes it otherwise
+    pub(crate) fn toggle(&mut self, piece: Piece, pos: Position) {
+        self.all ^= bit(pos);
+        match piece.color {
+            Color::White => self.white ^= bit(pos),
+            Color::Black => self.black ^= bit(pos),
+        }
+    }
+
+    pub(crate) fn is_color(&self, pos: Position, color: Color) -> bool {
+        let pieces = match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        };
+        pieces & bit(pos) != 0
+    }
+
+    // First occupied square when walking from (but not including) the given square in one of the eight sliding directions
+    pub(crate) fn first_blocker(&self, from: Position, direction: (i32, i32)) -> Option<Position> {
+        let blockers = ray(from, direction) & self.all;
+        if blockers == 0 {
+            return None;
+        }
+
+        // Walking towards a higher index (down the board, or right along a rank) meets the lowest set bit first
+        let index = if direction.0 - 8 * direction.1 > 0 {
+            blockers.trailing_zeros()
+        } else {
+            63 - blockers.leading_zeros()
+        };
+        Position::from_index(index as u8)
+    }
+
+    // Number of empty squares from (but not including) the given square up to the first piece or the edge of the board
+    pub(crate) fn empty_ray_len(&self, from: Position, direction: (i32, i32)) -> i32 {
+        match self.first_blocker(from, direction) {
+            Some(blocker) => (blocker.x as i32 - from.x as i32).abs().max((blocker.y as i32 - from.y as i32).abs()) - 1,
+            None => ray(from, direction).count_ones() as i32,
+        }
+    }
+}
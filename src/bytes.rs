@@ -0,0 +1,159 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{Color, Game, ParsedFen, Piece, PieceType, Position, Square};
+
+// 32 bytes of squares (two per byte, a8 first), a flags byte, the en passant pawn and the two clocks
+const SQUARE_BYTES: usize = 8 * 8 / 2;
+const FLAGS: usize = SQUARE_BYTES;
+const EN_PASSANT: usize = FLAGS + 1;
+const HALFMOVE_CLOCK: usize = EN_PASSANT + 1;
+const FULLMOVE_NUMBER: usize = HALFMOVE_CLOCK + 4;
+const BYTE_COUNT: usize = FULLMOVE_NUMBER + 4;
+
+// Flag bits: black to move, then the castling rights in fen order (KQkq)
+const BLACK_TO_MOVE: u8 = 1;
+const CASTLING_FLAGS: [u8; 4] = [1 << 1, 1 << 2, 1 << 3, 1 << 4];
+const NO_EN_PASSANT: u8 = 0xff;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum BytesError {
+    WrongLength(usize),
+    BadSquare(u8),
+    BadFlags(u8),
+    BadEnPassant(u8),
+    BadFullmoveNumber(u32),
+}
+
+impl fmt::Display for BytesError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BytesError::WrongLength(len) => write!(f, "expected {} bytes, got {}", BYTE_COUNT, len),
+            BytesError::BadSquare(nibble) => write!(f, "invalid square value {}", nibble),
+            BytesError::BadFlags(flags) => write!(f, "invalid flags {:#04x}", flags),
+            BytesError::BadEnPassant(index) => write!(f, "invalid en passant square index {}", index),
+            BytesError::BadFullmoveNumber(number) => write!(f, "fullmove number must be at least 1, got {}", number),
+        }
+    }
+}
+
+impl Error for BytesError {}
+
+// Low three bits are the piece type (0 for an empty square), the fourth bit is set for black
+fn square_to_nibble(square: Square) -> u8 {
+    match square {
+        Some(piece) => {
+            let piece_bits = match piece.piece_type {
+                PieceType::Pawn => 1,
+                PieceType::Knight => 2,
+                PieceType::Bishop => 3,
+                PieceType::Rook => 4,
+                PieceType::Queen => 5,
+                PieceType::King => 6,
+            };
+            if piece.color == Color::Black {
+                piece_bits | 0b1000
+            } else {
+                piece_bits
+            }
+        }
+        None => 0,
+    }
+}
+
+fn nibble_to_square(nibble: u8) -> Result<Square, BytesError> {
+    if nibble == 0 {
+        return Ok(None);
+    }
+
+    let piece_type = match nibble & 0b0111 {
+        1 => PieceType::Pawn,
+        2 => PieceType::Knight,
+        3 => PieceType::Bishop,
+        4 => PieceType::Rook,
+        5 => PieceType::Queen,
+        6 => PieceType::King,
+        _ => return Err(BytesError::BadSquare(nibble)),
+    };
+    let color = if nibble & 0b1000 == 0 { Color::White } else { Color::Black };
+    Ok(Some(Piece { piece_type, color }))
+}
+
+impl Game {
+    // Compact binary encoding of the position, including the clocks but not the history or the optional rules
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(BYTE_COUNT);
+        for pair in self.squares.chunks(2) {
+            bytes.push(square_to_nibble(pair[0]) << 4 | square_to_nibble(pair[1]));
+        }
+
+        let mut flags = if self.turn == Color::Black { BLACK_TO_MOVE } else { 0 };
+        let castling_available = [
+            self.white_castling_kingside_available,
+            self.white_castling_queenside_available,
+            self.black_castling_kingside_available,
+            self.black_castling_queenside_available,
+        ];
+        for (available, flag) in castling_available.iter().zip(CASTLING_FLAGS) {
+            if *available {
+                flags |= flag;
+            }
+        }
+        bytes.push(flags);
+
+        bytes.push(match self.en_passant_susceptible_pawn {
+            Some(pawn) => pawn.to_index() as u8,
+            None => NO_EN_PASSANT,
+        });
+        bytes.extend_from_slice(&self.halfmove_clock.to_le_bytes());
+        bytes.extend_from_slice(&self.fullmove_number.to_le_bytes());
+        bytes
+    }
+
+    // Creates a game from bytes made by to_bytes, with the optional rules set like in Game::new
+    pub fn from_bytes(bytes: &[u8]) -> Result<Game, BytesError> {
+        if bytes.len() != BYTE_COUNT {
+            return Err(BytesError::WrongLength(bytes.len()));
+        }
+
+        let mut squares: [Square; 8 * 8] = [None; 8 * 8];
+        for (i, byte) in bytes[..SQUARE_BYTES].iter().enumerate() {
+            squares[2 * i] = nibble_to_square(byte >> 4)?;
+            squares[2 * i + 1] = nibble_to_square(byte & 0x0f)?;
+        }
+
+        let flags = bytes[FLAGS];
+        if flags & !(BLACK_TO_MOVE | CASTLING_FLAGS.iter().fold(0, |all, flag| all | flag)) != 0 {
+            return Err(BytesError::BadFlags(flags));
+        }
+
+        let turn = if flags & BLACK_TO_MOVE == 0 { Color::White } else { Color::Black };
+
+        // the pawn has to be on the rank it lands on after moving two squares, as seen by the side to move
+        let en_passant_rank = if turn == Color::White { 4 } else { 3 };
+        let en_passant_susceptible_pawn = match bytes[EN_PASSANT] {
+            NO_EN_PASSANT => None,
+            index => match Position::from_index(index) {
+                Some(pawn) if pawn.y == en_passant_rank => Some(pawn),
+                _ => return Err(BytesError::BadEnPassant(index)),
+            },
+        };
+
+        let clock = |start: usize| u32::from_le_bytes(bytes[start..start + 4].try_into().unwrap());
+        let fullmove_number = clock(FULLMOVE_NUMBER);
+        if fullmove_number == 0 {
+            return Err(BytesError::BadFullmoveNumber(fullmove_number));
+        }
+
+        let mut game = Game::new();
+        game.load_parsed_fen(ParsedFen {
+            squares,
+            turn,
+            en_passant_susceptible_pawn,
+            castling_available: CASTLING_FLAGS.map(|flag| flags & flag != 0),
+            halfmove_clock: clock(HALFMOVE_CLOCK),
+            fullmove_number,
+        });
+        Ok(game)
+    }
+}
@@ -1,15 +1,29 @@
+pub mod bitboard;
+pub mod bytes;
 pub mod moves;
+pub mod pgn;
+pub mod render;
+pub mod san;
 pub mod tests;
+#[cfg(feature = "wasm")]
+pub mod wasm;
+pub mod zobrist;
+use crate::bitboard::Occupancy;
 use crate::moves::*;
+use crate::zobrist::piece_key;
+pub use crate::bytes::BytesError;
+pub use crate::moves::LegalMoves;
+pub use crate::pgn::{PgnError, PgnTags};
+pub use crate::render::Orientation;
+pub use crate::san::SanError;
+use std::error::Error;
+use std::fmt;
+use std::hash::{Hash, Hasher};
 use std::ops::Not;
+use std::str::FromStr;
 
 // TODO
-// Finish fen parsing error handling
 // Finish documentation
-// (low priority) Make a function to get king positions (might be useful for displaying warning on king when checked)
-// (low priority) Export board to fen string
-// (low priority) Implement threefold repetition
-// (low priority) Validation to make sure there are 2 kings on the board
 
 #[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub struct Position {
@@ -19,31 +33,133 @@ pub struct Position {
 
 impl Position {
     pub fn new(x: u8, y: u8) -> Self {
+        match Self::try_new(x, y) {
+            Ok(position) => position,
+            Err(_) => panic!("Attempt to initialize Position with out of bounds coordinates. Valid range is 0-7."),
+        }
+    }
+
+    pub fn try_new(x: u8, y: u8) -> Result<Self, PositionError> {
         if x > 7 || y > 7 {
-            panic!("Attempt to initialize Position with out of bounds coordinates. Valid range is 0-7.");
+            return Err(PositionError::OutOfBounds);
         }
-        Self { x, y }
+        Ok(Self { x, y })
+    }
+
+    // Parses algebraic notation such as "e4"
+    pub fn from_string(string: &str) -> Option<Self> {
+        string.parse().ok()
+    }
+
+    // Whether x and y are both 0-7. Positions made through new, try_new or parsing always are, but the fields
+    // are public so one could be built by hand with anything in them.
+    pub fn is_on_board(&self) -> bool {
+        self.x <= 7 && self.y <= 7
     }
 
-    pub fn from_string(string: &str) -> Self {
+    // Index into Game::squares, which starts at a8 (index 0) and goes rank by rank down to h1 (index 63).
+    // The position has to be on the board, otherwise the index is meaningless.
+    pub fn to_index(&self) -> usize {
+        debug_assert!(self.is_on_board(), "position {:?} is off the board", self);
+        8 * 8 - 8 - self.y as usize * 8 + self.x as usize
+    }
+
+    pub fn from_index(i: u8) -> Option<Self> {
+        if i >= 8 * 8 {
+            return None;
+        }
+        Some(Self { x: i % 8, y: 7 - i / 8 })
+    }
+}
+
+// Parses algebraic notation such as "e4", the file may be uppercase
+impl FromStr for Position {
+    type Err = PositionParseError;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
         let chars: Vec<char> = string.chars().collect();
+        if chars.len() != 2 {
+            return Err(PositionParseError::WrongLength);
+        }
 
         let file = chars[0].to_ascii_lowercase();
         let x = match file {
             'a'..='h' => (file as u8) - b'a',
-            _ => panic!("Attempt to initialize Position with invalid algebraic notation."),
+            _ => return Err(PositionParseError::BadFile(chars[0])),
         };
 
-        let rank = chars[1].to_ascii_lowercase();
+        let rank = chars[1];
         let y = match rank {
             '1'..='8' => (rank as u8) - b'1',
-            _ => panic!("Attempt to initialize Position with invalid algebraic notation."),
+            _ => return Err(PositionParseError::BadRank(rank)),
         };
 
-        Self { x, y }
+        Ok(Self { x, y })
+    }
+}
+
+// Same as Position::new, so out of bounds coordinates panic (use Position::try_new to avoid that)
+impl From<(u8, u8)> for Position {
+    fn from((x, y): (u8, u8)) -> Self {
+        Self::new(x, y)
+    }
+}
+
+impl From<Position> for (u8, u8) {
+    fn from(position: Position) -> Self {
+        (position.x, position.y)
+    }
+}
+
+impl TryFrom<&str> for Position {
+    type Error = PositionParseError;
+
+    fn try_from(string: &str) -> Result<Self, Self::Error> {
+        string.parse()
+    }
+}
+
+// Formats the position in algebraic notation, e.g. "e4"
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}{}", (b'a' + self.x) as char, (b'1' + self.y) as char)
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PositionError {
+    OutOfBounds,
+}
+
+impl fmt::Display for PositionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionError::OutOfBounds => write!(f, "coordinates must be in the range 0-7"),
+        }
+    }
+}
+
+impl Error for PositionError {}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PositionParseError {
+    WrongLength,
+    BadFile(char),
+    BadRank(char),
+}
+
+impl fmt::Display for PositionParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionParseError::WrongLength => write!(f, "position must be exactly 2 characters"),
+            PositionParseError::BadFile(chr) => write!(f, "invalid file '{}', must be a-h", chr),
+            PositionParseError::BadRank(chr) => write!(f, "invalid rank '{}', must be 1-8", chr),
+        }
     }
 }
 
+impl Error for PositionParseError {}
+
 #[derive(Clone, Copy)]
 pub struct PositionBuilder {
     position: Option<Position>,
@@ -103,16 +219,201 @@ pub enum MoveResult {
     Disallowed,
 }
 
+impl From<Result<Move, MoveError>> for MoveResult {
+    fn from(result: Result<Move, MoveError>) -> Self {
+        match result {
+            Ok(_) => MoveResult::Allowed,
+            Err(_) => MoveResult::Disallowed,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveError {
+    EmptySource,
+    WrongTurn,
+    FriendlyFire,
+    IllegalPattern,
+    WouldBeInCheck,
+    GameOver,
+    AwaitingPromotion,
+}
+
+impl fmt::Display for MoveError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MoveError::EmptySource => write!(f, "there is no piece to move"),
+            MoveError::WrongTurn => write!(f, "it is the other color's turn"),
+            MoveError::FriendlyFire => write!(f, "can't capture a piece of the same color"),
+            MoveError::IllegalPattern => write!(f, "the piece can't move like that"),
+            MoveError::WouldBeInCheck => write!(f, "the move would leave the king in check"),
+            MoveError::GameOver => write!(f, "the game is over"),
+            MoveError::AwaitingPromotion => write!(f, "a pawn has to be promoted first"),
+        }
+    }
+}
+
+impl Error for MoveError {}
+
+// A move that has been made, along with what happened during it
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Move {
+    pub from: Position,
+    pub to: Position,
+    // The piece that moved, as it was before the move (a pawn when promoting)
+    pub piece: Piece,
+    pub captured: Option<Piece>,
+    pub is_castle: bool,
+    pub is_en_passant: bool,
+    pub promotion: Option<PieceType>,
+}
+
+// How far a move gets through validation, returned by Game::move_legality
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MoveLegality {
+    // The piece can't move like that at all
+    Illegal,
+    // The piece could move like that, but it would leave its own king in check (or castle out of or through check)
+    LeavesKingInCheck,
+    Legal,
+}
+
+// A legal destination of a piece, with what kind of move it would be, returned by Game::legal_destinations
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct MoveHint {
+    pub to: Position,
+    pub is_castle: bool,
+    pub is_en_passant: bool,
+    // The pawn reaches the last rank, so a promotion piece has to be picked
+    pub promotes: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FenError {
+    WrongSegmentCount,
+    WrongRankCount,
+    BadPieceChar(char),
+    RankNotEightSquares,
+    BadActiveColor,
+    BadCastling,
+    BadEnPassantSquare,
+    BadClock,
+    // Only reported by validate_fen, load_fen accepts boards set up without kings
+    WrongKingCount(Color),
+}
+
+impl fmt::Display for FenError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FenError::WrongSegmentCount => write!(f, "fen string must consist of 6 segments"),
+            FenError::WrongRankCount => write!(f, "board segment must consist of 8 ranks"),
+            FenError::BadPieceChar(chr) => write!(f, "invalid piece character '{}'", chr),
+            FenError::RankNotEightSquares => write!(f, "every rank must describe exactly 8 squares"),
+            FenError::BadActiveColor => write!(f, "active color must be either 'w' or 'b'"),
+            FenError::BadCastling => write!(f, "castling ability must be '-' or some of 'KQkq'"),
+            FenError::BadEnPassantSquare => write!(f, "invalid en passant target square"),
+            FenError::BadClock => write!(f, "halfmove clock and fullmove number must be whole numbers"),
+            FenError::WrongKingCount(color) => write!(f, "{:?} must have exactly one king", color),
+        }
+    }
+}
+
+impl Error for FenError {}
+
+// Problems that make a position impossible to reach in a real game
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum PositionLegalityError {
+    WrongKingCount(Color),
+    TooManyPieces(Color),
+    TooManyPawns(Color),
+    PawnOnBackRank(Position),
+    OpponentInCheck,
+}
+
+impl fmt::Display for PositionLegalityError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PositionLegalityError::WrongKingCount(color) => write!(f, "{:?} must have exactly one king", color),
+            PositionLegalityError::TooManyPieces(color) => write!(f, "{:?} has more than 16 pieces", color),
+            PositionLegalityError::TooManyPawns(color) => write!(f, "{:?} has more than 8 pawns", color),
+            PositionLegalityError::PawnOnBackRank(pos) => write!(f, "pawn on the first or last rank at {}", pos),
+            PositionLegalityError::OpponentInCheck => write!(f, "the side not to move is in check"),
+        }
+    }
+}
+
+impl Error for PositionLegalityError {}
+
+// Problems with the placements given to Game::setup or Game::set_piece
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SetupError {
+    BadPosition(PositionParseError),
+    DuplicateKing(Color),
+}
+
+impl fmt::Display for SetupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SetupError::BadPosition(error) => write!(f, "invalid position: {}", error),
+            SetupError::DuplicateKing(color) => write!(f, "{:?} has more than one king", color),
+        }
+    }
+}
+
+impl Error for SetupError {}
+
 #[derive(Copy, Clone, Debug, PartialEq)]
 pub enum GameState {
     Normal,
     Check(Color),
     Checkmate(Color),
+    // The given color has resigned
+    Resignation(Color),
+    // The given color has been stalemated, a draw unless stalemate_is_draw is turned off in which case it loses
+    Stalemate(Color),
+    // The given color has won by reaching the center with its king, only in king of the hill
+    KingOfTheHill(Color),
+    // The given color has won by giving three checks, only in three-check
+    ThreeCheck(Color),
+    // The given color has won by blowing up the enemy king, only in atomic
+    Atomic(Color),
+    // Fifty moves by each side without a capture or pawn move
+    FiftyMoveRule,
+    // The same position has occurred three times
+    Repetition,
+    // Neither side has enough pieces left to checkmate
+    InsufficientMaterial,
+    // Any other draw, such as by agreement
     Draw,
     AwaitingPromotion(Position),
 }
 
-#[derive(Copy, Clone, PartialEq)]
+// Who has won, as opposed to GameState::Checkmate which names the side that lost
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameResult {
+    WhiteWins,
+    BlackWins,
+    Draw,
+    Ongoing,
+}
+
+// Why a game that is over has ended. The colors mean the same as in GameState: the side that was checkmated
+// or resigned, and the side that won a variant.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Termination {
+    Checkmate(Color),
+    Stalemate,
+    FiftyMove,
+    Repetition,
+    InsufficientMaterial,
+    Resignation(Color),
+    Agreement,
+    KingOfTheHill(Color),
+    ThreeCheck(Color),
+    Atomic(Color),
+}
+
+#[derive(Copy, Clone, PartialEq, Debug, Hash)]
 pub enum PieceType {
     Pawn,
     Knight,
@@ -122,12 +423,105 @@ pub enum PieceType {
     King,
 }
 
-#[derive(Copy, Clone, PartialEq, Debug)]
+impl PieceType {
+    // Material values in pawns, the king has none since it can't be traded
+    pub const PAWN_VALUE: u32 = 1;
+    pub const KNIGHT_VALUE: u32 = 3;
+    pub const BISHOP_VALUE: u32 = 3;
+    pub const ROOK_VALUE: u32 = 5;
+    pub const QUEEN_VALUE: u32 = 9;
+    pub const KING_VALUE: u32 = 0;
+
+    pub const fn value(&self) -> u32 {
+        match self {
+            PieceType::Pawn => Self::PAWN_VALUE,
+            PieceType::Knight => Self::KNIGHT_VALUE,
+            PieceType::Bishop => Self::BISHOP_VALUE,
+            PieceType::Rook => Self::ROOK_VALUE,
+            PieceType::Queen => Self::QUEEN_VALUE,
+            PieceType::King => Self::KING_VALUE,
+        }
+    }
+
+    // The capitalized english name, e.g. "Knight"
+    pub const fn name(&self) -> &'static str {
+        match self {
+            PieceType::Pawn => "Pawn",
+            PieceType::Knight => "Knight",
+            PieceType::Bishop => "Bishop",
+            PieceType::Rook => "Rook",
+            PieceType::Queen => "Queen",
+            PieceType::King => "King",
+        }
+    }
+
+    // The lowercase fen letter of the piece type
+    pub fn to_fen_char(&self) -> char {
+        match self {
+            PieceType::Pawn => 'p',
+            PieceType::Knight => 'n',
+            PieceType::Bishop => 'b',
+            PieceType::Rook => 'r',
+            PieceType::Queen => 'q',
+            PieceType::King => 'k',
+        }
+    }
+
+    // Parses a fen letter, ignoring its case
+    pub fn from_fen_char(chr: char) -> Option<PieceType> {
+        match chr.to_ascii_lowercase() {
+            'p' => Some(PieceType::Pawn),
+            'n' => Some(PieceType::Knight),
+            'b' => Some(PieceType::Bishop),
+            'r' => Some(PieceType::Rook),
+            'q' => Some(PieceType::Queen),
+            'k' => Some(PieceType::King),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for PieceType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen_char())
+    }
+}
+
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash)]
 pub enum Color {
     Black,
     White,
 }
 
+impl Color {
+    // The fen letter of the color, 'w' or 'b'
+    pub fn to_fen_char(&self) -> char {
+        match self {
+            Color::White => 'w',
+            Color::Black => 'b',
+        }
+    }
+
+    pub fn from_fen_char(chr: char) -> Option<Color> {
+        match chr {
+            'w' => Some(Color::White),
+            'b' => Some(Color::Black),
+            _ => None,
+        }
+    }
+
+    // The other color, same as !color
+    pub fn opponent(self) -> Color {
+        !self
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.to_fen_char())
+    }
+}
+
 impl Not for Color {
     type Output = Color;
 
@@ -139,25 +533,224 @@ impl Not for Color {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq, Debug, Hash)]
 pub struct Piece {
     pub piece_type: PieceType,
     pub color: Color,
 }
 
+impl Piece {
+    // The fen letter of the piece, uppercase for white and lowercase for black
+    pub fn to_fen_char(&self) -> char {
+        match self.color {
+            Color::White => self.piece_type.to_fen_char().to_ascii_uppercase(),
+            Color::Black => self.piece_type.to_fen_char(),
+        }
+    }
+}
+
 pub type Square = Option<Piece>;
 
+// Everything apply_move changes, so that undo_move can put it back
+#[derive(Copy, Clone)]
+struct UndoInfo {
+    from: Position,
+    to: Position,
+    moved: Piece,
+    captured: Square,
+    // Differs from to when capturing en passant
+    captured_pos: Position,
+    rook_move: Option<(Position, Position)>,
+    // Pieces around the capture that were blown up along with the capturing piece, only in atomic
+    exploded: [Option<(Position, Piece)>; 8],
+    turn: Color,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    en_passant_susceptible_pawn: Option<Position>,
+    castling_available: [bool; 4],
+    last_move: Option<(Position, Position)>,
+}
+
+// Which castling moves are still available, castling might still be blocked or illegal right now
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct CastlingRights {
+    pub white_kingside: bool,
+    pub white_queenside: bool,
+    pub black_kingside: bool,
+    pub black_queenside: bool,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CastlingSide {
+    Kingside,
+    Queenside,
+}
+
+impl CastlingRights {
+    pub fn can_castle(&self, color: Color, side: CastlingSide) -> bool {
+        match (color, side) {
+            (Color::White, CastlingSide::Kingside) => self.white_kingside,
+            (Color::White, CastlingSide::Queenside) => self.white_queenside,
+            (Color::Black, CastlingSide::Kingside) => self.black_kingside,
+            (Color::Black, CastlingSide::Queenside) => self.black_queenside,
+        }
+    }
+}
+
 #[derive(Clone)]
 pub struct Game {
-    pub squares: [Square; 8 * 8],
-    pub turn: Color,
-    pub game_state: GameState,
-    pub moves_since_capture: u32,
-    pub en_passant_susceptible_pawn: Option<Position>,
-    pub white_castling_kingside_available: bool,
-    pub white_castling_queenside_available: bool,
-    pub black_castling_kingside_available: bool,
-    pub black_castling_queenside_available: bool
+    squares: [Square; 8 * 8],
+    turn: Color,
+    game_state: GameState,
+    halfmove_clock: u32,
+    fullmove_number: u32,
+    en_passant_susceptible_pawn: Option<Position>,
+    white_castling_kingside_available: bool,
+    white_castling_queenside_available: bool,
+    black_castling_kingside_available: bool,
+    black_castling_queenside_available: bool,
+    // From and to of the most recent move (the king's when castling)
+    last_move: Option<(Position, Position)>,
+    // Number of times each color has given check since the game was loaded
+    white_checks_given: u32,
+    black_checks_given: u32,
+    // Optional rules, set up with GameBuilder
+    fifty_move_rule: bool,
+    automatic_draws: bool,
+    stalemate_is_draw: bool,
+    king_of_the_hill: bool,
+    three_check: bool,
+    atomic: bool,
+    position_history: Vec<u64>,
+    move_history: Vec<Move>,
+    // Position the move history starts from
+    initial_fen: String,
+    // Updated along with the board, see zobrist.rs
+    zobrist_hash: u64,
+    // Updated along with the board, see bitboard.rs
+    occupancy: Occupancy,
+    // Color that has offered a draw which hasn't been accepted or declined yet
+    draw_offer: Option<Color>,
+}
+
+// Games are equal when their positions are: the squares, turn, castling rights and en passant pawn.
+// The halfmove clock, fullmove number, game state and histories are left out.
+impl PartialEq for Game {
+    fn eq(&self, other: &Self) -> bool {
+        self.squares == other.squares
+            && self.turn == other.turn
+            && self.white_castling_kingside_available == other.white_castling_kingside_available
+            && self.white_castling_queenside_available == other.white_castling_queenside_available
+            && self.black_castling_kingside_available == other.black_castling_kingside_available
+            && self.black_castling_queenside_available == other.black_castling_queenside_available
+            && self.en_passant_susceptible_pawn == other.en_passant_susceptible_pawn
+    }
+}
+
+impl Eq for Game {}
+
+// The zobrist hash covers the same parts of the position as the equality above
+impl Hash for Game {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.zobrist_hash.hash(state);
+    }
+}
+
+// A draw that the players can claim but that doesn't end the game by itself
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum DrawClaim {
+    FiftyMoveRule,
+    ThreefoldRepetition,
+}
+
+// Sets up a game with optional rules turned on or off, the defaults are like in Game::new
+pub struct GameBuilder {
+    fifty_move_rule: bool,
+    automatic_draws: bool,
+    stalemate_is_draw: bool,
+    king_of_the_hill: bool,
+    three_check: bool,
+    atomic: bool,
+    starting_fen: String,
+}
+
+impl GameBuilder {
+    pub fn new() -> GameBuilder {
+        GameBuilder {
+            fifty_move_rule: true,
+            automatic_draws: false,
+            stalemate_is_draw: true,
+            king_of_the_hill: false,
+            three_check: false,
+            atomic: false,
+            starting_fen: String::from("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"),
+        }
+    }
+
+    pub fn fifty_move_rule(mut self, enabled: bool) -> Self {
+        self.fifty_move_rule = enabled;
+        self
+    }
+
+    // When enabled, the fifty-move rule and threefold repetition end the game without being claimed
+    pub fn automatic_draws(mut self, enabled: bool) -> Self {
+        self.automatic_draws = enabled;
+        self
+    }
+
+    // When disabled, the stalemated side loses instead
+    pub fn stalemate_is_draw(mut self, enabled: bool) -> Self {
+        self.stalemate_is_draw = enabled;
+        self
+    }
+
+    // When enabled, the first side to get its king to d4, e4, d5 or e5 wins
+    pub fn king_of_the_hill(mut self, enabled: bool) -> Self {
+        self.king_of_the_hill = enabled;
+        self
+    }
+
+    // When enabled, the first side to give check three times wins
+    pub fn three_check(mut self, enabled: bool) -> Self {
+        self.three_check = enabled;
+        self
+    }
+
+    // When enabled, captures blow up the capturing piece and every piece but pawns next to it,
+    // and blowing up the enemy king wins
+    pub fn atomic(mut self, enabled: bool) -> Self {
+        self.atomic = enabled;
+        self
+    }
+
+    pub fn starting_fen(mut self, fen: &str) -> Self {
+        self.starting_fen = String::from(fen);
+        self
+    }
+
+    pub fn build(self) -> Result<Game, FenError> {
+        let mut game = Game::new();
+        game.fifty_move_rule = self.fifty_move_rule;
+        game.automatic_draws = self.automatic_draws;
+        game.stalemate_is_draw = self.stalemate_is_draw;
+        game.king_of_the_hill = self.king_of_the_hill;
+        game.three_check = self.three_check;
+        game.atomic = self.atomic;
+        game.load_fen(&self.starting_fen)?;
+        Ok(game)
+    }
+}
+
+impl Default for GameBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Default for Game {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Game {
@@ -166,302 +759,1235 @@ impl Game {
             squares: [None; 8 * 8],
             turn: Color::White,
             game_state: GameState::Normal,
-            moves_since_capture: 0,
+            halfmove_clock: 0,
+            fullmove_number: 1,
             en_passant_susceptible_pawn: None,
             white_castling_kingside_available: true,
             white_castling_queenside_available: true,
             black_castling_kingside_available: true,
-            black_castling_queenside_available: true
+            black_castling_queenside_available: true,
+            last_move: None,
+            white_checks_given: 0,
+            black_checks_given: 0,
+            fifty_move_rule: true,
+            automatic_draws: false,
+            stalemate_is_draw: true,
+            king_of_the_hill: false,
+            three_check: false,
+            atomic: false,
+            position_history: Vec::new(),
+            move_history: Vec::new(),
+            initial_fen: String::new(),
+            zobrist_hash: 0,
+            occupancy: Occupancy::default(),
+            draw_offer: None,
         };
 
-        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-        
+        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1")
+            .expect("starting fen should be valid");
+
+        game
+    }
+
+    // A game at the starting position with the same optional rules as this one
+    pub(crate) fn new_with_same_rules(&self) -> Self {
+        let mut game = Self::new();
+        game.fifty_move_rule = self.fifty_move_rule;
+        game.automatic_draws = self.automatic_draws;
+        game.stalemate_is_draw = self.stalemate_is_draw;
+        game.king_of_the_hill = self.king_of_the_hill;
+        game.three_check = self.three_check;
+        game.atomic = self.atomic;
         game
     }
 
+    // Builds a game straight from a fen string instead of the starting position
+    pub fn from_fen(fen: &str) -> Result<Self, FenError> {
+        let mut game = Self::new();
+        game.load_fen(fen)?;
+        Ok(game)
+    }
+
+    // Builds a game from ranks of squares, rank 8 first and each rank from the a-file to the h-file, so that
+    // board[0][0] is a8 and board[7][7] is h1. No castling rights or en passant are set, and the clocks start over.
+    pub fn from_array(board: [[Square; 8]; 8], turn: Color) -> Self {
+        let mut game = Self::new();
+        game.load_parsed_fen(ParsedFen {
+            squares: board.concat().try_into().expect("eight ranks of eight squares"),
+            turn,
+            en_passant_susceptible_pawn: None,
+            castling_available: [false; 4],
+            halfmove_clock: 0,
+            fullmove_number: 1,
+        });
+        game
+    }
+
+    // The board in the layout from_array takes, rank 8 first
+    pub fn to_array(&self) -> [[Square; 8]; 8] {
+        let mut board = [[None; 8]; 8];
+        for (rank, squares) in board.iter_mut().zip(self.squares.chunks(8)) {
+            rank.copy_from_slice(squares);
+        }
+        board
+    }
+
     pub fn clear_board(&mut self) {
         self.squares = [None; 8 * 8];
         self.turn = Color::White;
         self.game_state = GameState::Normal;
-        self.moves_since_capture = 0;
+        self.halfmove_clock = 0;
+        self.fullmove_number = 1;
         self.en_passant_susceptible_pawn = None;
+        // there are no kings or rooks left to castle with
+        self.white_castling_kingside_available = false;
+        self.white_castling_queenside_available = false;
+        self.black_castling_kingside_available = false;
+        self.black_castling_queenside_available = false;
+        self.position_history.clear();
+        self.move_history.clear();
+        self.last_move = None;
+        self.white_checks_given = 0;
+        self.black_checks_given = 0;
+        self.draw_offer = None;
+        self.zobrist_hash = self.compute_zobrist_hash();
+        self.occupancy = Occupancy::from_squares(&self.squares);
+    }
+
+    pub fn get_square(&self, position: Position) -> Square {
+        self.squares[position.to_index()]
+    }
+
+    // Same as get_square, but None instead of a panic (or the wrong square) for a position that is off the board
+    pub fn try_get_square(&self, position: Position) -> Option<Square> {
+        if !position.is_on_board() {
+            return None;
+        }
+        Some(self.get_square(position))
+    }
+
+    // Changes a square by hand, the move history starts over from the edited position. The game state isn't updated.
+    pub fn set_square(&mut self, position: Position, value: Square) {
+        self.put_square(position, value);
+        self.restart_history();
+    }
+
+    // Changes a square and keeps the zobrist hash and occupancy up to date, used when making and taking back moves
+    fn put_square(&mut self, position: Position, value: Square) {
+        if let Some(piece) = self.get_square(position) {
+            self.zobrist_hash ^= piece_key(piece, position);
+            self.occupancy.toggle(piece, position);
+        }
+        if let Some(piece) = value {
+            self.zobrist_hash ^= piece_key(piece, position);
+            self.occupancy.toggle(piece, position);
+        }
+        self.squares[position.to_index()] = value;
+    }
+
+    // Same as get_square, but with the position in algebraic notation (e.g. "e4"), None if it can't be parsed
+    pub fn get_square_str(&self, position: &str) -> Option<Square> {
+        position.parse().ok().map(|position| self.get_square(position))
+    }
+
+    // Same as set_square, but with the position in algebraic notation (e.g. "e4")
+    pub fn set_square_str(&mut self, position: &str, value: Square) -> Result<(), PositionParseError> {
+        self.set_square(position.parse()?, value);
+        Ok(())
+    }
+
+    pub fn is_empty_square(&self, position: Position) -> bool {
+        self.get_square(position).is_none()
+    }
+
+    // Number of pieces on the board, of both colors
+    pub fn piece_count(&self) -> usize {
+        self.squares.iter().filter(|square| square.is_some()).count()
+    }
+
+    // Checks that the position could occur in a real game, returning the first problem found
+    pub fn validate_position(&self) -> Result<(), PositionLegalityError> {
+        for color in [Color::White, Color::Black] {
+            let pieces: Vec<(Position, Piece)> = self.pieces_of(color).collect();
+            let count_of = |piece_type: PieceType| pieces.iter().filter(|(_, piece)| piece.piece_type == piece_type).count();
+
+            if count_of(PieceType::King) != 1 {
+                return Err(PositionLegalityError::WrongKingCount(color));
+            }
+            if pieces.len() > 16 {
+                return Err(PositionLegalityError::TooManyPieces(color));
+            }
+            if count_of(PieceType::Pawn) > 8 {
+                return Err(PositionLegalityError::TooManyPawns(color));
+            }
+            if let Some((pos, _)) = pieces.iter().find(|(pos, piece)| piece.piece_type == PieceType::Pawn && (pos.y == 0 || pos.y == 7)) {
+                return Err(PositionLegalityError::PawnOnBackRank(*pos));
+            }
+        }
+
+        // the side to move would be able to capture the king
+        if self.is_in_check(!self.turn) {
+            return Err(PositionLegalityError::OpponentInCheck);
+        }
+
+        Ok(())
+    }
+
+    // Puts a piece on the board, replacing whatever stood there, and updates the game state. The move history
+    // starts over from the edited position. Nothing is changed if it would give a color a second king.
+    pub fn set_piece(&mut self, pos: Position, piece_type: PieceType, color: Color) -> Result<(), SetupError> {
+        let piece = Piece { piece_type, color };
+        if piece_type == PieceType::King && self.pieces_of(color).any(|(other, other_piece)| other != pos && other_piece == piece) {
+            return Err(SetupError::DuplicateKing(color));
+        }
+
+        self.put_square(pos, Some(piece));
+        self.restart_history();
+        self.game_state = check_game_state(self);
+        Ok(())
+    }
+
+    // Clears the square and updates the game state, the move history starts over from the edited position
+    pub fn remove_piece(&mut self, pos: Position) {
+        self.put_square(pos, None);
+        self.restart_history();
+        self.game_state = check_game_state(self);
+    }
+
+    // Forgets the moves made so far, so that the histories start from the current (edited) position
+    fn restart_history(&mut self) {
+        self.position_history = vec![self.zobrist_hash];
+        self.move_history.clear();
+        self.last_move = None;
+    }
+
+    // Clears the board and puts the given pieces on it, with white to move. Nothing is changed if a position
+    // can't be parsed or a color gets more than one king.
+    pub fn setup(&mut self, placements: &[(&str, PieceType, Color)]) -> Result<(), SetupError> {
+        let mut pieces = Vec::with_capacity(placements.len());
+        for (position, piece_type, color) in placements {
+            let position: Position = position.parse().map_err(SetupError::BadPosition)?;
+            pieces.push((position, Piece { piece_type: *piece_type, color: *color }));
+        }
+        for color in [Color::White, Color::Black] {
+            let kings = pieces.iter().filter(|(_, piece)| *piece == Piece { piece_type: PieceType::King, color });
+            if kings.count() > 1 {
+                return Err(SetupError::DuplicateKing(color));
+            }
+        }
+
+        self.clear_board();
+        for (position, piece) in pieces {
+            self.put_square(position, Some(piece));
+        }
+        self.restart_history();
+        self.game_state = check_game_state(self);
+        Ok(())
+    }
+
+    // Gives the move to the given color and updates the game state. Handing the move over drops the en passant pawn,
+    // since it could only be taken by the side that was to move. The move history starts over from here.
+    pub fn set_turn(&mut self, color: Color) {
+        self.zobrist_hash ^= self.state_key();
+        if color != self.turn {
+            self.en_passant_susceptible_pawn = None;
+        }
+        self.turn = color;
+        self.zobrist_hash ^= self.state_key();
+        self.restart_history();
+        self.game_state = check_game_state(self);
+    }
+
+    // All moves made since the game was loaded, in order
+    pub fn move_history(&self) -> &[Move] {
+        &self.move_history
+    }
+
+    // The internal representation of the board, index 0 is a8 and 63 is h1
+    pub fn squares(&self) -> &[Square; 8 * 8] {
+        &self.squares
+    }
+
+    // Every square along with its position in fen order: a8, b8, ..., h8, a7, ..., h1
+    pub fn squares_in_rank_order(&self) -> impl Iterator<Item = (Position, Square)> + '_ {
+        self.squares
+            .iter()
+            .enumerate()
+            .map(|(i, square)| (Position::from_index(i as u8).expect("squares has 64 entries"), *square))
+    }
+
+    pub fn turn(&self) -> Color {
+        self.turn
+    }
+
+    pub fn game_state(&self) -> GameState {
+        self.game_state
+    }
+
+    pub fn halfmove_clock(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn fullmove_number(&self) -> u32 {
+        self.fullmove_number
+    }
+
+    // Number of halfmoves made since the game was loaded, unlike fullmove_number this starts at 0
+    // and counts both colors' moves, no matter what move number the loaded fen started at
+    pub fn ply(&self) -> u32 {
+        self.move_history.len() as u32
+    }
+
+    pub fn en_passant_susceptible_pawn(&self) -> Option<Position> {
+        self.en_passant_susceptible_pawn
+    }
+
+    // The square the en passant susceptible pawn skipped over, where a capturing pawn would land
+    pub fn en_passant_target(&self) -> Option<Position> {
+        self.en_passant_susceptible_pawn
+            .map(|pawn| Position::new(pawn.x, if pawn.y == 3 { 2 } else { 5 }))
+    }
+
+    pub fn castling_rights(&self) -> CastlingRights {
+        CastlingRights {
+            white_kingside: self.white_castling_kingside_available,
+            white_queenside: self.white_castling_queenside_available,
+            black_kingside: self.black_castling_kingside_available,
+            black_queenside: self.black_castling_queenside_available,
+        }
+    }
+
+    // If the castling right is still available, castling might still be blocked or illegal right now
+    pub fn can_castle(&self, color: Color, side: CastlingSide) -> bool {
+        self.castling_rights().can_castle(color, side)
+    }
+
+    // From and to of the most recent move (the king's when castling)
+    pub fn last_move(&self) -> Option<(Position, Position)> {
+        self.last_move
+    }
+
+    // Number of times the given color has given check since the game was loaded
+    pub fn checks_given(&self, color: Color) -> u32 {
+        match color {
+            Color::White => self.white_checks_given,
+            Color::Black => self.black_checks_given,
+        }
+    }
+
+    pub fn fifty_move_rule(&self) -> bool {
+        self.fifty_move_rule
+    }
+
+    pub fn automatic_draws(&self) -> bool {
+        self.automatic_draws
+    }
+
+    pub fn stalemate_is_draw(&self) -> bool {
+        self.stalemate_is_draw
+    }
+
+    pub fn king_of_the_hill(&self) -> bool {
+        self.king_of_the_hill
+    }
+
+    pub fn three_check(&self) -> bool {
+        self.three_check
+    }
+
+    pub fn atomic(&self) -> bool {
+        self.atomic
+    }
+
+    #[deprecated(note = "use halfmove_clock instead")]
+    pub fn moves_since_capture(&self) -> u32 {
+        self.halfmove_clock
+    }
+
+    pub fn get_king_position(&self, color: Color) -> Option<Position> {
+        self.pieces_of(color)
+            .find(|(_, piece)| piece.piece_type == PieceType::King)
+            .map(|(pos, _)| pos)
+    }
+
+    pub fn is_in_check(&self, color: Color) -> bool {
+        check_check(self).is_in_check(color)
+    }
+
+    pub fn get_game_result(&self) -> GameResult {
+        match self.game_state {
+            GameState::Checkmate(Color::White) => GameResult::BlackWins,
+            GameState::Checkmate(Color::Black) => GameResult::WhiteWins,
+            GameState::Resignation(Color::White) => GameResult::BlackWins,
+            GameState::Resignation(Color::Black) => GameResult::WhiteWins,
+            GameState::Stalemate(_) if self.stalemate_is_draw => GameResult::Draw,
+            GameState::Stalemate(Color::White) => GameResult::BlackWins,
+            GameState::Stalemate(Color::Black) => GameResult::WhiteWins,
+            GameState::KingOfTheHill(Color::White) => GameResult::WhiteWins,
+            GameState::KingOfTheHill(Color::Black) => GameResult::BlackWins,
+            GameState::ThreeCheck(Color::White) => GameResult::WhiteWins,
+            GameState::ThreeCheck(Color::Black) => GameResult::BlackWins,
+            GameState::Atomic(Color::White) => GameResult::WhiteWins,
+            GameState::Atomic(Color::Black) => GameResult::BlackWins,
+            GameState::FiftyMoveRule | GameState::Repetition | GameState::InsufficientMaterial | GameState::Draw => {
+                GameResult::Draw
+            }
+            _ => GameResult::Ongoing,
+        }
+    }
+
+    // Why the game has ended, None while it's still going (or waiting for a promotion)
+    pub fn termination(&self) -> Option<Termination> {
+        match self.game_state {
+            GameState::Checkmate(color) => Some(Termination::Checkmate(color)),
+            GameState::Stalemate(_) => Some(Termination::Stalemate),
+            GameState::FiftyMoveRule => Some(Termination::FiftyMove),
+            GameState::Repetition => Some(Termination::Repetition),
+            GameState::InsufficientMaterial => Some(Termination::InsufficientMaterial),
+            GameState::Resignation(color) => Some(Termination::Resignation(color)),
+            GameState::Draw => Some(Termination::Agreement),
+            GameState::KingOfTheHill(color) => Some(Termination::KingOfTheHill(color)),
+            GameState::ThreeCheck(color) => Some(Termination::ThreeCheck(color)),
+            GameState::Atomic(color) => Some(Termination::Atomic(color)),
+            GameState::Normal | GameState::Check(_) | GameState::AwaitingPromotion(_) => None,
+        }
+    }
+
+    // The given color gives up, ending the game in favor of the opponent (does nothing if the game is already over)
+    pub fn resign(&mut self, color: Color) {
+        if self.get_game_result() == GameResult::Ongoing {
+            self.game_state = GameState::Resignation(color);
+            self.draw_offer = None;
+        }
+    }
+
+    // The offer stands until the opponent accepts it or makes a move
+    pub fn offer_draw(&mut self, color: Color) -> MoveResult {
+        if self.get_game_result() != GameResult::Ongoing {
+            return MoveResult::Disallowed;
+        }
+        self.draw_offer = Some(color);
+        MoveResult::Allowed
+    }
+
+    // Ends the game in a draw if the opponent of the given color has offered one
+    pub fn accept_draw(&mut self, color: Color) -> MoveResult {
+        if self.draw_offer != Some(!color) || self.get_game_result() != GameResult::Ongoing {
+            return MoveResult::Disallowed;
+        }
+        self.game_state = GameState::Draw;
+        self.draw_offer = None;
+        MoveResult::Allowed
+    }
+
+    pub fn draw_offer(&self) -> Option<Color> {
+        self.draw_offer
+    }
+
+    // The draw the side to move could claim, threefold repetition or fifty moves by each side without a capture or pawn move.
+    // Only the seventy-five move rule and fivefold repetition end the game by themselves, unless automatic_draws is on.
+    pub fn can_claim_draw(&self) -> Option<DrawClaim> {
+        if self.get_game_result() != GameResult::Ongoing || matches!(self.game_state, GameState::AwaitingPromotion(_)) {
+            return None;
+        }
+
+        if self.repetition_count() >= 3 {
+            Some(DrawClaim::ThreefoldRepetition)
+        } else if self.fifty_move_rule && self.halfmove_clock >= 100 {
+            Some(DrawClaim::FiftyMoveRule)
+        } else {
+            None
+        }
+    }
+
+    // Ends the game in a draw if one can be claimed
+    pub fn claim_draw(&mut self) -> MoveResult {
+        self.game_state = match self.can_claim_draw() {
+            Some(DrawClaim::ThreefoldRepetition) => GameState::Repetition,
+            Some(DrawClaim::FiftyMoveRule) => GameState::FiftyMoveRule,
+            None => return MoveResult::Disallowed,
+        };
+        self.draw_offer = None;
+        MoveResult::Allowed
+    }
+
+    // Number of times the current position has occurred, including now
+    fn repetition_count(&self) -> usize {
+        self.position_history.iter().filter(|hash| **hash == self.zobrist_hash).count()
+    }
+
+    // Every piece of the given color along with its position
+    pub fn pieces_of(&self, color: Color) -> impl Iterator<Item = (Position, Piece)> + '_ {
+        (0..8)
+            .flat_map(|y| (0..8).map(move |x| Position::new(x, y)))
+            .filter_map(move |pos| match self.get_square(pos) {
+                Some(piece) if piece.color == color => Some((pos, piece)),
+                _ => None,
+            })
+    }
+
+    // Positions of every piece of the given type and color
+    pub fn find_pieces(&self, piece_type: PieceType, color: Color) -> Vec<Position> {
+        self.pieces_of(color)
+            .filter(|(_, piece)| piece.piece_type == piece_type)
+            .map(|(pos, _)| pos)
+            .collect()
+    }
+
+    // Material of the given color in pawns (the king isn't counted)
+    pub fn count_material(&self, color: Color) -> u32 {
+        self.pieces_of(color).map(|(_, piece)| piece.piece_type.value()).sum()
+    }
+
+    // White's material minus black's
+    pub fn material_balance(&self) -> i32 {
+        self.count_material(Color::White) as i32 - self.count_material(Color::Black) as i32
+    }
+
+    // Whether neither side can possibly checkmate: only kings are left, or a single knight or bishop,
+    // or only bishops that all stand on squares of the same color
+    pub fn is_insufficient_material(&self) -> bool {
+        let pieces: Vec<(Position, Piece)> = self
+            .pieces_of(Color::White)
+            .chain(self.pieces_of(Color::Black))
+            .filter(|(_, piece)| piece.piece_type != PieceType::King)
+            .collect();
+
+        match pieces[..] {
+            [] => true,
+            [(_, piece)] => matches!(piece.piece_type, PieceType::Knight | PieceType::Bishop),
+            _ => {
+                let on_light_square = |pos: &Position| (pos.x + pos.y) % 2 == 1;
+                let first_on_light = on_light_square(&pieces[0].0);
+                pieces
+                    .iter()
+                    .all(|(pos, piece)| piece.piece_type == PieceType::Bishop && on_light_square(pos) == first_on_light)
+            }
+        }
+    }
+
+    // Positions of the pieces giving check to the side to move (two of them in a double check)
+    pub fn checking_pieces(&self) -> Vec<Position> {
+        match self.get_king_position(self.turn) {
+            Some(king_pos) => self.get_attackers(king_pos, !self.turn),
+            None => Vec::new(),
+        }
+    }
+
+    // Pieces of the given color that can't leave the line between their king and an enemy slider,
+    // paired with the position of the pinning piece
+    pub fn pinned_pieces(&self, color: Color) -> Vec<(Position, Position)> {
+        let king_pos = match self.get_king_position(color) {
+            Some(king_pos) => king_pos,
+            None => return Vec::new(),
+        };
+
+        let mut pinned = Vec::new();
+        for (direction, piece_type) in [
+            ((0, 1), PieceType::Rook),
+            ((1, 0), PieceType::Rook),
+            ((0, -1), PieceType::Rook),
+            ((-1, 0), PieceType::Rook),
+            ((1, 1), PieceType::Bishop),
+            ((1, -1), PieceType::Bishop),
+            ((-1, -1), PieceType::Bishop),
+            ((-1, 1), PieceType::Bishop),
+        ] {
+            // The first piece from the king has to be our own and the second an enemy slider along this line
+            let mut pieces = Vec::new();
+            let mut builder = PositionBuilder::set(king_pos).walk(direction);
+            while let Some(current) = builder.build() {
+                if let Some(piece) = self.get_square(current) {
+                    pieces.push((current, piece));
+                    if pieces.len() == 2 {
+                        break;
+                    }
+                }
+                builder = builder.walk(direction);
+            }
+
+            if let [(pinned_pos, pinned_piece), (pinner_pos, pinner)] = pieces[..] {
+                if pinned_piece.color == color
+                    && pinner.color != color
+                    && (pinner.piece_type == piece_type || pinner.piece_type == PieceType::Queen)
+                {
+                    pinned.push((pinned_pos, pinner_pos));
+                }
+            }
+        }
+        pinned
+    }
+
+    pub fn load_fen(&mut self, fen: &str) -> Result<(), FenError> {
+        // parsed separately so that self is left untouched on error
+        let parsed = parse_fen(fen, false)?;
+        self.load_parsed_fen(parsed);
+        Ok(())
+    }
+
+    // Same as load_fen, but also accepts fens without the halfmove clock and fullmove number,
+    // which then default to 0 and 1
+    pub fn load_fen_lenient(&mut self, fen: &str) -> Result<(), FenError> {
+        let parsed = parse_fen(fen, true)?;
+        self.load_parsed_fen(parsed);
+        Ok(())
+    }
+
+    fn load_parsed_fen(&mut self, parsed: ParsedFen) {
+        self.squares = parsed.squares;
+        self.turn = parsed.turn;
+        self.en_passant_susceptible_pawn = parsed.en_passant_susceptible_pawn;
+        [
+            self.white_castling_kingside_available,
+            self.white_castling_queenside_available,
+            self.black_castling_kingside_available,
+            self.black_castling_queenside_available,
+        ] = parsed.castling_available;
+        self.halfmove_clock = parsed.halfmove_clock;
+        self.fullmove_number = parsed.fullmove_number;
+
+        // start a fresh repetition history from the loaded position
+        self.zobrist_hash = self.compute_zobrist_hash();
+        self.occupancy = Occupancy::from_squares(&self.squares);
+        self.position_history = vec![self.zobrist_hash];
+        self.move_history.clear();
+        self.last_move = None;
+        self.white_checks_given = 0;
+        self.black_checks_given = 0;
+        self.draw_offer = None;
+
+        // make sure to update game state, puzzles can start with a pawn waiting to be promoted
+        self.game_state = check_game_state(self);
+        if let Some(pos) = self.pending_promotion() {
+            self.game_state = GameState::AwaitingPromotion(pos);
+        }
+    }
+
+    // The same position seen from the other side: the board flipped top to bottom with the colors swapped,
+    // so the other color is to move. The optional rules are kept, the move history isn't.
+    pub fn mirror_vertical(&self) -> Game {
+        let mut squares: [Square; 8 * 8] = [None; 8 * 8];
+        for (i, square) in self.squares.iter().enumerate() {
+            let mirrored_index = (7 - i / 8) * 8 + i % 8;
+            squares[mirrored_index] = square.map(|piece| Piece { piece_type: piece.piece_type, color: !piece.color });
+        }
+
+        let mut game = self.clone();
+        game.load_parsed_fen(ParsedFen {
+            squares,
+            turn: !self.turn,
+            en_passant_susceptible_pawn: self.en_passant_susceptible_pawn.map(|pawn| Position::new(pawn.x, 7 - pawn.y)),
+            castling_available: [
+                self.black_castling_kingside_available,
+                self.black_castling_queenside_available,
+                self.white_castling_kingside_available,
+                self.white_castling_queenside_available,
+            ],
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+        });
+        game
+    }
+
+    // Only the first segment of the fen, the pieces on the board, e.g. "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR"
+    pub fn fen_piece_placement(&self) -> String {
+        let mut board = String::new();
+        let mut empty_squares = 0;
+        for (pos, square) in self.squares_in_rank_order() {
+            match square {
+                Some(piece) => {
+                    if empty_squares > 0 {
+                        board.push_str(&empty_squares.to_string());
+                        empty_squares = 0;
+                    }
+                    board.push(piece.to_fen_char());
+                }
+                None => empty_squares += 1,
+            }
+
+            // end of a rank
+            if pos.x == 7 {
+                if empty_squares > 0 {
+                    board.push_str(&empty_squares.to_string());
+                    empty_squares = 0;
+                }
+                if pos.y > 0 {
+                    board.push('/');
+                }
+            }
+        }
+        board
+    }
+
+    pub fn to_fen(&self) -> String {
+        // segment 1: board
+        let board = self.fen_piece_placement();
+
+        // segment 3: castling ability
+        let mut castling = String::new();
+        let castling_rights = self.castling_rights();
+        for (color, side, chr) in [
+            (Color::White, CastlingSide::Kingside, 'K'),
+            (Color::White, CastlingSide::Queenside, 'Q'),
+            (Color::Black, CastlingSide::Kingside, 'k'),
+            (Color::Black, CastlingSide::Queenside, 'q'),
+        ] {
+            if castling_rights.can_castle(color, side) {
+                castling.push(chr);
+            }
+        }
+        if castling.is_empty() {
+            castling.push('-');
+        }
+
+        // segment 4: en passant target square
+        let en_passant = match self.en_passant_target() {
+            Some(target) => target.to_string(),
+            None => String::from("-"),
+        };
+
+        // segment 5 & 6: halfmove clock and fullmove counter
+        format!(
+            "{} {} {} {} {} {}",
+            board, self.turn, castling, en_passant, self.halfmove_clock, self.fullmove_number
+        )
+    }
+
+    fn pseudo_validate_move(&self, from: Position, to: Position) -> bool {
+        let source_square = self.get_square(from);
+
+        if source_square.is_none() {
+            return false;
+        }
+
+        let source_square = source_square.unwrap();
+
+        match source_square.piece_type {
+            PieceType::Pawn => pseudo_validate_pawn_move(self, from, to),
+            PieceType::Knight => pseudo_validate_knight_move(self, from, to),
+            PieceType::Bishop => pseudo_validate_bishop_move(self, from, to),
+            PieceType::Rook => pseudo_validate_rook_move(self, from, to),
+            PieceType::Queen => pseudo_validate_queen_move(self, from, to),
+            PieceType::King => pseudo_validate_king_move(self, from, to),
+        }
+    }
+
+    // Whether any piece of the given color could capture a piece standing at pos (which may be empty).
+    // Pinned pieces still count as attackers.
+    pub fn is_square_attacked(&self, pos: Position, by: Color) -> bool {
+        self.attackers(pos, by).next().is_some()
+    }
+
+    // Positions of every piece of the given color that could capture a piece standing at pos (which may be empty).
+    // Pinned pieces still count as attackers.
+    pub fn get_attackers(&self, pos: Position, by: Color) -> Vec<Position> {
+        self.attackers(pos, by).collect()
     }
 
-    pub fn get_square(&self, position: Position) -> Square {
-        self.squares[8 * 8 - 8 - position.y as usize * 8 + position.x as usize]
+    // Squares the piece at pos attacks, whatever stands on them, so that q is in attacks_from(p) exactly when
+    // p is in get_attackers(q, ..). Pawns only attack diagonally, kings don't castle and sliders stop at the first piece.
+    pub fn attacks_from(&self, pos: Position) -> Vec<Position> {
+        let piece = match self.get_square(pos) {
+            Some(piece) => piece,
+            None => return Vec::new(),
+        };
+        let base_builder = PositionBuilder::set(pos).color(piece.color);
+
+        let (offsets, slides): (&[(i32, i32)], bool) = match piece.piece_type {
+            PieceType::Pawn => {
+                return [(-1, 0), (1, 0)]
+                    .into_iter()
+                    .filter_map(|offset| base_builder.forward(1).walk(offset).build())
+                    .collect();
+            }
+            PieceType::Knight => (&[(-1, 2), (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1)], false),
+            PieceType::King => (&[(-1, 1), (0, 1), (1, 1), (-1, 0), (1, 0), (-1, -1), (0, -1), (1, -1)], false),
+            PieceType::Rook => (&[(0, 1), (1, 0), (0, -1), (-1, 0)], true),
+            PieceType::Bishop => (&[(1, 1), (1, -1), (-1, -1), (-1, 1)], true),
+            PieceType::Queen => (&[(-1, 1), (0, 1), (1, 1), (-1, 0), (1, 0), (-1, -1), (0, -1), (1, -1)], true),
+        };
+
+        let mut attacks = Vec::new();
+        for direction in offsets {
+            let mut builder = base_builder.walk(*direction);
+            while let Some(current) = builder.build() {
+                attacks.push(current);
+                if !slides || self.get_square(current).is_some() {
+                    break;
+                }
+                builder = builder.walk(*direction);
+            }
+        }
+        attacks
     }
 
-    pub fn set_square(&mut self, position: Position, value: Square) {
-        self.squares[8 * 8 - 8 - position.y as usize * 8 + position.x as usize] = value;
+    // Looks outward from pos instead of going through every piece on the board, lazily so that callers can stop at the first attacker
+    fn attackers(&self, pos: Position, by: Color) -> impl Iterator<Item = Position> + '_ {
+        let base_builder = PositionBuilder::set(pos);
+        let is_attacker = move |pos: Position, piece_types: &[PieceType]| match self.get_square(pos) {
+            Some(piece) => piece.color == by && piece_types.contains(&piece.piece_type),
+            None => false,
+        };
+
+        // Knights
+        let knights = [(-1, 2), (1, 2), (2, 1), (2, -1), (1, -2), (-1, -2), (-2, -1), (-2, 1)]
+            .into_iter()
+            .filter_map(move |offset| base_builder.walk(offset).build())
+            .filter(move |pos| is_attacker(*pos, &[PieceType::Knight]));
+
+        // Kings (castling can't capture anything, so only the surrounding squares)
+        let kings = [(-1, 1), (0, 1), (1, 1), (-1, 0), (1, 0), (-1, -1), (0, -1), (1, -1)]
+            .into_iter()
+            .filter_map(move |offset| base_builder.walk(offset).build())
+            .filter(move |pos| is_attacker(*pos, &[PieceType::King]));
+
+        // Pawns only attack diagonally forward, so look diagonally backward from the pawn's point of view
+        let pawn_direction = if by == Color::White { -1 } else { 1 };
+        let pawns = [-1, 1]
+            .into_iter()
+            .filter_map(move |x| base_builder.walk((x, pawn_direction)).build())
+            .filter(move |pos| is_attacker(*pos, &[PieceType::Pawn]));
+
+        // Sliding pieces, the first piece in each direction is the only one that can attack (found with the occupancy bitboards)
+        let sliders = [
+            ((0, 1), PieceType::Rook),
+            ((1, 0), PieceType::Rook),
+            ((0, -1), PieceType::Rook),
+            ((-1, 0), PieceType::Rook),
+            ((1, 1), PieceType::Bishop),
+            ((1, -1), PieceType::Bishop),
+            ((-1, -1), PieceType::Bishop),
+            ((-1, 1), PieceType::Bishop),
+        ]
+        .into_iter()
+        .filter_map(move |(direction, piece_type)| {
+            self.occupancy
+                .first_blocker(pos, direction)
+                .filter(|pos| is_attacker(*pos, &[piece_type, PieceType::Queen]))
+        });
+
+        knights.chain(kings).chain(pawns).chain(sliders)
     }
 
-    pub fn load_fen(&mut self, fen: &str) {
-        // Clear board
-        self.squares.iter_mut().for_each(|square| *square = None);
+    // Makes a move on the board without any validation, updating everything but the game state and histories.
+    // The move has to be pseudo legal, and can be taken back with undo_move.
+    fn apply_move(&mut self, from: Position, to: Position) -> UndoInfo {
+        let moved = self.get_square(from).expect("applied move should have a piece to move");
+
+        let mut undo = UndoInfo {
+            from,
+            to,
+            moved,
+            captured: self.get_square(to),
+            captured_pos: to,
+            rook_move: None,
+            exploded: [None; 8],
+            turn: self.turn,
+            halfmove_clock: self.halfmove_clock,
+            fullmove_number: self.fullmove_number,
+            en_passant_susceptible_pawn: self.en_passant_susceptible_pawn,
+            castling_available: [
+                self.white_castling_kingside_available,
+                self.white_castling_queenside_available,
+                self.black_castling_kingside_available,
+                self.black_castling_queenside_available,
+            ],
+            last_move: self.last_move,
+        };
+
+        // The side to move, castling rights and en passant file are hashed in again once they're updated
+        self.zobrist_hash ^= self.state_key();
 
-        let segments: Vec<&str> = fen.split(" ").collect();
+        // En passant captures the pawn next to the target square (detected by a pawn moving diagonally to an empty square)
+        if moved.piece_type == PieceType::Pawn && from.x != to.x && undo.captured.is_none() {
+            undo.captured_pos = Position::new(to.x, from.y);
+            undo.captured = self.get_square(undo.captured_pos);
+            self.put_square(undo.captured_pos, None);
+        }
 
-        if segments.len() != 6 {
-            return; // ERROR
+        // Castling also moves the rook next to the king
+        let move_diff = to.x as i32 - from.x as i32;
+        if moved.piece_type == PieceType::King && move_diff.abs() == 2 {
+            let rook_move = if move_diff > 0 {
+                (Position::new(7, from.y), Position::new(to.x - 1, from.y))
+            } else {
+                (Position::new(0, from.y), Position::new(to.x + 1, from.y))
+            };
+            self.put_square(rook_move.1, self.get_square(rook_move.0));
+            self.put_square(rook_move.0, None);
+            undo.rook_move = Some(rook_move);
         }
 
-        let board_segments: Vec<&str> = segments[0].split("/").collect();
-        if board_segments.len() != 8 {
-            return; // ERROR
+        // disable castling availability if moving rook / king
+        if moved.piece_type == PieceType::King {
+            match moved.color {
+                Color::Black => {
+                    self.black_castling_queenside_available = false;
+                    self.black_castling_kingside_available = false;
+                },
+                Color::White => {
+                    self.white_castling_queenside_available = false;
+                    self.white_castling_kingside_available = false;
+                }
+            }
+        }
+        if moved.piece_type == PieceType::Rook {
+            self.disable_castling_from_corner(from);
         }
 
-        // Parse segment 1: Board
-        for (seg_index, seg) in board_segments.iter().enumerate() {
-            let mut filled_tiles = 0;
+        // disable castling availability if a rook gets captured in its corner
+        self.disable_castling_from_corner(to);
 
-            for chr in seg.chars() {
-                if chr.is_ascii_digit() {
-                    filled_tiles += chr.to_digit(10).unwrap() as usize;
-                    continue;
+        // Make the move
+        self.put_square(to, Some(moved));
+        self.put_square(from, None);
+
+        // In atomic, a capture blows up the capturing piece and every piece but pawns around it
+        // (around the square the pawn lands on when capturing en passant)
+        if self.atomic && undo.captured.is_some() {
+            self.put_square(to, None);
+            let neighbors = [(-1, 1), (0, 1), (1, 1), (-1, 0), (1, 0), (-1, -1), (0, -1), (1, -1)]
+                .into_iter()
+                .filter_map(|offset| PositionBuilder::set(to).walk(offset).build());
+            for (exploded, pos) in undo.exploded.iter_mut().zip(neighbors) {
+                if let Some(piece) = self.get_square(pos).filter(|piece| piece.piece_type != PieceType::Pawn) {
+                    *exploded = Some((pos, piece));
+                    self.put_square(pos, None);
+                    self.disable_castling_from_corner(pos);
                 }
+            }
+        }
 
-                let color = if chr.is_uppercase() {
-                    Color::White
-                } else {
-                    Color::Black
-                };
-                let piece: PieceType = match chr.to_ascii_lowercase() {
-                    'p' => PieceType::Pawn,
-                    'r' => PieceType::Rook,
-                    'n' => PieceType::Knight,
-                    'b' => PieceType::Bishop,
-                    'q' => PieceType::Queen,
-                    'k' => PieceType::King,
-                    _ => return, // ERROR
-                };
-
-                self.squares[seg_index * 8 + filled_tiles] = Some(Piece {
-                    piece_type: piece,
-                    color,
-                });
-                filled_tiles += 1;
-            }
-
-            if filled_tiles != 8 {
-                return; // ERROR
-            }
-        }
-
-        // Parse segment 2: Turn
-        self.turn = match segments[1] {
-            "w" => Color::White,
-            "b" => Color::Black,
-            _ => return, // ERROR
-        };
+        // Captures and pawn moves reset the fifty-move rule clock
+        self.halfmove_clock += 1;
+        if undo.captured.is_some() || moved.piece_type == PieceType::Pawn {
+            self.halfmove_clock = 0;
+        }
 
-        // segment 3: castling ability
-        self.black_castling_kingside_available = segments[2].contains("k");
-        self.black_castling_queenside_available = segments[2].contains("q");
-        self.white_castling_kingside_available = segments[2].contains("K");
-        self.white_castling_queenside_available = segments[2].contains("Q");
+        // A full move has been made once black has moved
+        if self.turn == Color::Black {
+            self.fullmove_number += 1;
+        }
 
-        // segment 4: en passant target square
-        self.en_passant_susceptible_pawn = match segments[3] {
-            "-" => None,
-            _ => Some(Position::from_string(segments[3]))
-        };
+        // Change the turn
+        self.turn = self.turn.opponent();
 
-        // segment 5: halfmove clock
-        if let Ok(n) = segments[4].parse::<u32>() { self.moves_since_capture = n };
+        // En passant susceptibility logic
+        self.en_passant_susceptible_pawn = None;
+        if moved.piece_type == PieceType::Pawn && (from.y as i32 - to.y as i32).abs() == 2 {
+            self.en_passant_susceptible_pawn = Some(to);
+        }
 
-        // segment 6: fullmove counter (quite irrelevant, might skip)
+        self.zobrist_hash ^= self.state_key();
+        self.last_move = Some((from, to));
 
-        // make sure to update game state
-        self.game_state = check_game_state(self);
+        undo
     }
 
-    fn pseudo_validate_move(&self, from: Position, to: Position) -> bool {
-        let source_square = self.get_square(from);
+    // Takes back a move made with apply_move (including any promotion of the moved pawn)
+    fn undo_move(&mut self, undo: UndoInfo) {
+        self.put_square(undo.to, None);
+        self.put_square(undo.captured_pos, undo.captured);
+        self.put_square(undo.from, Some(undo.moved));
 
-        if source_square.is_none() {
-            return false;
+        if let Some((rook_from, rook_to)) = undo.rook_move {
+            self.put_square(rook_from, self.get_square(rook_to));
+            self.put_square(rook_to, None);
         }
 
-        let source_square = source_square.unwrap();
+        for (pos, piece) in undo.exploded.into_iter().flatten() {
+            self.put_square(pos, Some(piece));
+        }
 
-        match source_square.piece_type {
-            PieceType::Pawn => pseudo_validate_pawn_move(&self, from, to),
-            PieceType::Knight => pseudo_validate_knight_move(&self, from, to),
-            PieceType::Bishop => pseudo_validate_bishop_move(&self, from, to),
-            PieceType::Rook => pseudo_validate_rook_move(&self, from, to),
-            PieceType::Queen => pseudo_validate_queen_move(&self, from, to),
-            PieceType::King => pseudo_validate_king_move(&self, from, to),
+        self.zobrist_hash ^= self.state_key();
+        self.turn = undo.turn;
+        self.halfmove_clock = undo.halfmove_clock;
+        self.fullmove_number = undo.fullmove_number;
+        self.en_passant_susceptible_pawn = undo.en_passant_susceptible_pawn;
+        [
+            self.white_castling_kingside_available,
+            self.white_castling_queenside_available,
+            self.black_castling_kingside_available,
+            self.black_castling_queenside_available,
+        ] = undo.castling_available;
+        self.last_move = undo.last_move;
+        self.zobrist_hash ^= self.state_key();
+    }
+
+    // A rook leaving or losing its corner takes the castling right on that side with it
+    fn disable_castling_from_corner(&mut self, pos: Position) {
+        match pos {
+            Position{x: 0, y: 0} => self.white_castling_queenside_available = false,
+            Position{x: 7, y: 0} => self.white_castling_kingside_available = false,
+            Position{x: 0, y: 7} => self.black_castling_queenside_available = false,
+            Position{x: 7, y: 7} => self.black_castling_kingside_available = false,
+            _ => {}
+        }
+    }
+
+    // Whether the piece at from can make the move, and if not, whether that's because of the way it moves or because
+    // of its king. Only the move itself is looked at, not whose turn it is or whether the game is over.
+    pub fn move_legality(&self, from: Position, to: Position) -> MoveLegality {
+        let capturing_own_piece = match (self.get_square(from), self.get_square(to)) {
+            (Some(piece), Some(target)) => piece.color == target.color,
+            _ => false,
+        };
+        if from == to || capturing_own_piece {
+            return MoveLegality::Illegal;
         }
+        self.clone().check_move_legality(from, to)
     }
 
-    fn validate_move(&self, from: Position, to: Position) -> bool {
+    fn validate_move(&mut self, from: Position, to: Position) -> bool {
+        self.check_move_legality(from, to) == MoveLegality::Legal
+    }
+
+    // The pseudo legal check first, then everything that has to do with the king
+    fn check_move_legality(&mut self, from: Position, to: Position) -> MoveLegality {
         if !self.pseudo_validate_move(from, to) {
-            return false;
+            return MoveLegality::Illegal;
         }
 
         // some castling validation
+        let source_square: Piece = self.get_square(from).unwrap();
         {
             let x_diff = to.x as i32 - from.x as i32;
-            if x_diff.abs() == 2 {
-                let source_square: Piece = self.get_square(from).unwrap();
-
-                // cant perform castling when in check
-                if self.game_state == GameState::Check(source_square.color) {
-                    return false;
-                }
-
-                // make sure king stepping pos isn't in check
-                let res: i32 = from.x as i32 + x_diff/2;
-                let stepping_pos = Position::new(res as u8, from.y);
-                let mut new_game = self.clone();
-                new_game.set_square(stepping_pos, new_game.get_square(from));
-                new_game.set_square(from, None);
-                let state = check_game_state(&new_game);
-                if state == GameState::Check(source_square.color) {
-                    return false; 
+            if source_square.piece_type == PieceType::King && x_diff.abs() == 2 {
+                // cant castle out of, through or into check
+                for step in 0..=2 {
+                    let pos = Position::new((from.x as i32 + step * x_diff.signum()) as u8, from.y);
+                    if self.is_square_attacked(pos, !source_square.color) {
+                        return MoveLegality::LeavesKingInCheck;
+                    }
                 }
             }
         }
 
-        // Clone the board and simulate the move
-        let mut new_game = self.clone();
-        new_game.set_square(to, new_game.get_square(from));
-        new_game.set_square(from, None);
+        // In atomic, kings can't capture since they would blow themselves up
+        if self.atomic && source_square.piece_type == PieceType::King && self.get_square(to).is_some() {
+            return MoveLegality::Illegal;
+        }
 
-        let source_square: Piece = new_game.get_square(to).unwrap();
+        // Make the move and take it back again
+        let undo = self.apply_move(from, to);
+        // The move can't leave the moving side's own king in check. In atomic it can't blow up its own king
+        // either, but blowing up the enemy king wins even when in check.
+        let leaves_king_in_check = if self.atomic && self.get_king_position(source_square.color).is_none() {
+            true
+        } else if self.atomic && self.get_king_position(!source_square.color).is_none() {
+            false
+        } else {
+            check_check(self).is_in_check(source_square.color)
+        };
+        self.undo_move(undo);
 
-        match check_game_state(&new_game) {
-            GameState::Check(color) => source_square.color != color,
-            GameState::Checkmate(color) => source_square.color != color,
-            _ => true,
+        if leaves_king_in_check {
+            MoveLegality::LeavesKingInCheck
+        } else {
+            MoveLegality::Legal
         }
     }
 
-    pub fn make_move(&mut self, from: Position, to: Position) -> MoveResult {
-        // Can't move if awaiting promotion or in check
-        if matches!(self.game_state, GameState::AwaitingPromotion(_)) || matches!(self.game_state, GameState::Checkmate(_)){
+    // Makes a move and, if a pawn reaches the last rank, promotes it right away.
+    // The promotion piece is ignored for moves that don't promote.
+    pub fn make_move_promote(&mut self, from: Position, to: Position, promotion: PieceType) -> MoveResult {
+        if matches!(promotion, PieceType::King | PieceType::Pawn) {
+            return MoveResult::Disallowed;
+        }
+
+        if self.make_move(from, to).is_err() {
             return MoveResult::Disallowed;
         }
 
+        if let GameState::AwaitingPromotion(_) = self.game_state {
+            return self.promote(promotion);
+        }
+        MoveResult::Allowed
+    }
+
+    // Plays the moves in order, stopping at the first one that isn't allowed and returning its index.
+    // The moves before it stay made.
+    pub fn apply_moves(&mut self, moves: &[(Position, Position)]) -> Result<(), (usize, MoveError)> {
+        for (i, (from, to)) in moves.iter().enumerate() {
+            self.make_move(*from, *to).map_err(|error| (i, error))?;
+        }
+        Ok(())
+    }
+
+    // Same as apply_moves, but promotes to the promotion piece of each move (e.g. the moves of another game's move_history)
+    pub fn replay_moves(&mut self, moves: &[Move]) -> Result<(), (usize, MoveError)> {
+        for (i, made_move) in moves.iter().enumerate() {
+            if matches!(made_move.promotion, Some(PieceType::King | PieceType::Pawn)) {
+                return Err((i, MoveError::IllegalPattern));
+            }
+
+            self.make_move(made_move.from, made_move.to).map_err(|error| (i, error))?;
+            if let (Some(promotion), GameState::AwaitingPromotion(_)) = (made_move.promotion, self.game_state) {
+                self.promote(promotion);
+            }
+        }
+        Ok(())
+    }
+
+    // Use MoveResult::from on the result where only whether the move was allowed matters
+    pub fn make_move(&mut self, from: Position, to: Position) -> Result<Move, MoveError> {
+        // Can't move if awaiting promotion or once the game is over
+        if matches!(self.game_state, GameState::AwaitingPromotion(_)) {
+            return Err(MoveError::AwaitingPromotion);
+        }
+        if self.get_game_result() != GameResult::Ongoing {
+            return Err(MoveError::GameOver);
+        }
+
         let source_square = self.get_square(from);
         let target_square = self.get_square(to);
 
         // Move is invalid if the piece didn't move
         if from == to {
-            return MoveResult::Disallowed;
+            return Err(MoveError::IllegalPattern);
         }
 
         // Move is invalid if the source tile is empty
         if source_square.is_none() {
-            return MoveResult::Disallowed;
+            return Err(MoveError::EmptySource);
         }
 
         // Move is invalid if it's not the correct turn
         let source_square = source_square.unwrap();
         if source_square.color != self.turn {
-            return MoveResult::Disallowed;
+            return Err(MoveError::WrongTurn);
         }
 
-        let mut target_square_had_piece = false;
-
         // Prevent friendly fire
         if let Some(target_square) = target_square {
-            target_square_had_piece = true;
             if target_square.color == self.turn {
-                return MoveResult::Disallowed;
+                return Err(MoveError::FriendlyFire);
             }
         }
 
-        if !self.validate_move(from, to) {
-            return MoveResult::Disallowed;
+        match self.check_move_legality(from, to) {
+            MoveLegality::Illegal => return Err(MoveError::IllegalPattern),
+            // castling out of, through or into check also ends up here
+            MoveLegality::LeavesKingInCheck => return Err(MoveError::WouldBeInCheck),
+            MoveLegality::Legal => {}
         }
 
-        // below this line, the move WILL go through
-
-        // En passant should capture piece (detected by pawn moving diagonally without a piece in its target square)
-        if (from.x as i32 - to.x as i32).abs() == 1 && (from.y as i32 - to.y as i32).abs() == 1 
-            && !target_square_had_piece && source_square.piece_type == PieceType::Pawn {
-            self.set_square(Position::new(to.x, from.y), None);
-        }
+        Ok(self.commit_move(from, to))
+    }
 
-        // castling logic
-        // make the castling move (if one was made)
-        let move_diff = to.x as i32 - from.x as i32;
-        if source_square.piece_type == PieceType::King && move_diff.abs() == 2 {
-            if move_diff == -2 && ((source_square.color == Color::White && self.white_castling_queenside_available) || (source_square.color == Color::Black && self.black_castling_queenside_available)) {
-                self.set_square(Position::new(to.x + 1, from.y), self.get_square(Position::new(0, from.y)));
-                self.set_square(Position::new(0, from.y), None);
-            } else if move_diff == 2 && ((source_square.color == Color::White && self.white_castling_kingside_available) || (source_square.color == Color::Black && self.black_castling_kingside_available)) {
-                self.set_square(Position::new(to.x - 1, from.y), self.get_square(Position::new(7, from.y)));
-                self.set_square(Position::new(7, from.y), None);
-            }
+    // Makes a move known to be legal (e.g. from a database of games) without checking it, then promotes to its
+    // promotion piece if it has one. Castling and en passant are recognized from the board, like in make_move.
+    // Passing a move that isn't legal leaves the game in a broken state, checking that is up to the caller.
+    pub fn make_move_trusted(&mut self, trusted_move: &Move) {
+        self.commit_move(trusted_move.from, trusted_move.to);
+        if let (Some(promotion), GameState::AwaitingPromotion(_)) = (trusted_move.promotion, self.game_state) {
+            self.promote(promotion);
         }
+    }
 
-        // disable castling availability if moving rook / king  
-        if source_square.piece_type == PieceType::King {
-            match source_square.color {
-                Color::Black => {
-                    self.black_castling_queenside_available = false;
-                    self.black_castling_kingside_available = false;
-                },
-                Color::White => {
-                    self.white_castling_queenside_available = false;
-                    self.white_castling_kingside_available = false;
-                }
-            } 
-        }
-        if source_square.piece_type == PieceType::Rook {
-            match from {
-                Position{x: 0, y: 0} => self.white_castling_queenside_available = false,
-                Position{x: 7, y: 0} => self.white_castling_kingside_available = false,
-                Position{x: 0, y: 7} => self.black_castling_queenside_available = false,
-                Position{x: 7, y: 7} => self.black_castling_kingside_available = false,
-                _ => {}
-            }
+    // Makes a move that has already been validated and updates the game state and histories
+    fn commit_move(&mut self, from: Position, to: Position) -> Move {
+        // The history starts from wherever the first move is made, so that boards set up by hand are covered too
+        if self.move_history.is_empty() {
+            self.initial_fen = self.to_fen();
         }
 
-        // Make the move
-        self.set_square(to, Some(source_square));
-        self.set_square(from, None);
+        let piece = self.get_square(from).expect("committed move should start on a piece");
+        let undo = self.apply_move(from, to);
+        let made_move = Move {
+            from,
+            to,
+            piece,
+            captured: undo.captured,
+            is_castle: undo.rook_move.is_some(),
+            is_en_passant: undo.captured_pos != to,
+            promotion: None,
+        };
 
-        self.moves_since_capture += 1;
-        if target_square_had_piece {
-            self.moves_since_capture = 0;
+        // Moving instead of accepting declines the opponent's draw offer
+        if self.draw_offer == Some(self.turn) {
+            self.draw_offer = None;
         }
 
-        // Change the turn
-        self.turn = !self.turn;
+        // Remember the position for threefold repetition
+        self.position_history.push(self.zobrist_hash);
 
         // Update the game state
         self.game_state = check_game_state(self);
 
-        // En passant susceptibility logic
-        self.en_passant_susceptible_pawn = None;
-        if let Some(moved_piece) = self.get_square(to) {
-            if moved_piece.piece_type == PieceType::Pawn && (from.y as i32 - to.y as i32).abs() == 2
-            {
-                self.en_passant_susceptible_pawn = Some(to);
-            }
+        // Check for promotion, only the pawn that just moved can have reached the last rank (unless it exploded)
+        if undo.moved.piece_type == PieceType::Pawn && (to.y == 0 || to.y == 7) && self.get_square(to).is_some() {
+            self.game_state = GameState::AwaitingPromotion(to);
+        }
+
+        // A promotion can still change whether this move gives check, so it's counted when promoting instead
+        if !matches!(self.game_state, GameState::AwaitingPromotion(_)) {
+            self.count_check();
         }
 
-        // Check for promotion
+        self.move_history.push(made_move);
+
+        made_move
+    }
+
+    // Position of a pawn that has reached the last rank and has to be promoted, for positions loaded from a fen
+    fn pending_promotion(&self) -> Option<Position> {
         for x in 0..=7 {
             if let Some(piece) = self.get_square(Position::new(x, 0)) {
                 if piece.color == Color::Black && piece.piece_type == PieceType::Pawn {
-                    self.game_state = GameState::AwaitingPromotion(Position::new(x, 0));
-                    break;
+                    return Some(Position::new(x, 0));
                 }
             }
 
             if let Some(piece) = self.get_square(Position::new(x, 7)) {
                 if piece.color == Color::White && piece.piece_type == PieceType::Pawn {
-                    self.game_state = GameState::AwaitingPromotion(Position::new(x, 7));
-                    break;
+                    return Some(Position::new(x, 7));
                 }
             }
         }
+        None
+    }
 
-        MoveResult::Allowed
+    // Counts the check given by the move that was just made, which can win a three-check game
+    fn count_check(&mut self) {
+        let checked = match self.game_state {
+            GameState::Check(color) | GameState::Checkmate(color) => color,
+            _ => return,
+        };
+
+        let checks_given = match checked {
+            Color::White => &mut self.black_checks_given,
+            Color::Black => &mut self.white_checks_given,
+        };
+        *checks_given += 1;
+
+        if self.three_check && *checks_given >= 3 {
+            self.game_state = GameState::ThreeCheck(!checked);
+        }
+    }
+
+    pub fn simulate_move(&self, from: Position, to: Position) -> Option<Game> {
+        let mut new_game = self.clone();
+        new_game.make_move(from, to).ok().map(|_| new_game)
+    }
+
+    // Legal moves of the side to move that checkmate the opponent right away. A promotion counts if any of
+    // the promotion pieces gives mate.
+    pub fn checkmate_in_one(&self) -> Vec<(Position, Position)> {
+        let mated = GameState::Checkmate(!self.turn);
+        self.legal_moves()
+            .into_iter()
+            .filter(|(from, to)| match self.simulate_move(*from, *to) {
+                Some(game) if matches!(game.game_state, GameState::AwaitingPromotion(_)) => {
+                    [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight].into_iter().any(|piece_type| {
+                        let mut promoted = game.clone();
+                        promoted.promote(piece_type) == MoveResult::Allowed && promoted.game_state == mated
+                    })
+                }
+                Some(game) => game.game_state == mated,
+                None => false,
+            })
+            .collect()
     }
 
     pub fn promote(&mut self, new_type: PieceType) -> MoveResult {
@@ -478,7 +2004,7 @@ impl Game {
             match new_type {
                 PieceType::King | PieceType::Pawn => return MoveResult::Disallowed,
                 _ => {
-                    self.set_square(
+                    self.put_square(
                         pos,
                         Some(Piece {
                             piece_type: new_type,
@@ -488,36 +2014,189 @@ impl Game {
                 }
             };
 
+            if let Some(last_move) = self.move_history.last_mut() {
+                last_move.promotion = Some(new_type);
+            }
+
             self.game_state = check_game_state(self);
+            self.count_check();
             return MoveResult::Allowed;
         }
         
         MoveResult::Disallowed
     }
 
+    // Whether the side to move may move at all, which it can't once the game is over or while a promotion is pending.
+    // All the legal move methods come up empty otherwise, the same way make_move refuses every move.
+    pub(crate) fn can_make_moves(&self) -> bool {
+        self.get_game_result() == GameResult::Ongoing && !matches!(self.game_state, GameState::AwaitingPromotion(_))
+    }
+
+    // Legal moves of the piece at from, empty if there is no piece of the side to move there
     pub fn get_possible_moves(&self, from: Position) -> Vec<Position> {
-        let pseudo_possible_moves = self.get_pseudo_possible_moves(from);
+        match self.get_square(from) {
+            Some(piece) if piece.color == self.turn && self.can_make_moves() => {}
+            _ => return Vec::new(),
+        }
 
-        let mut possible_moves: Vec<Position> = Vec::new();
+        // Checking legality makes and takes back moves, so it needs a board of its own
+        self.clone().possible_moves(from)
+    }
 
-        for pseudo_possible_move in pseudo_possible_moves {
-            if self.validate_move(from, pseudo_possible_move) {
-                possible_moves.push(pseudo_possible_move);
-            }
+    // Same destinations as get_possible_moves, marked with whether they castle, capture en passant or promote
+    pub fn legal_destinations(&self, from: Position) -> Vec<MoveHint> {
+        let piece = match self.get_square(from) {
+            Some(piece) => piece,
+            None => return Vec::new(),
+        };
+
+        self.get_possible_moves(from)
+            .into_iter()
+            .map(|to| MoveHint {
+                to,
+                is_castle: piece.piece_type == PieceType::King && (to.x as i32 - from.x as i32).abs() == 2,
+                is_en_passant: piece.piece_type == PieceType::Pawn && from.x != to.x && self.get_square(to).is_none(),
+                promotes: self.move_promotes(from, to),
+            })
+            .collect()
+    }
+
+    // Whether the move takes a pawn to the last rank, where a promotion piece has to be picked. In atomic, a pawn
+    // that captures there is blown up instead.
+    pub(crate) fn move_promotes(&self, from: Position, to: Position) -> bool {
+        let is_pawn = self.get_square(from).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+        let explodes = self.atomic && self.get_square(to).is_some();
+        is_pawn && (to.y == 0 || to.y == 7) && !explodes
+    }
+
+    // Whether the move is legal and takes a piece, including en passant
+    pub fn is_capture(&self, from: Position, to: Position) -> bool {
+        if !self.get_possible_moves(from).contains(&to) {
+            return false;
         }
 
+        // a pawn moving diagonally always captures (en passant lands on an empty square)
+        let is_pawn = self.get_square(from).is_some_and(|piece| piece.piece_type == PieceType::Pawn);
+        self.get_square(to).is_some() || (is_pawn && from.x != to.x)
+    }
+
+    fn possible_moves(&mut self, from: Position) -> Vec<Position> {
+        let mut possible_moves = Vec::new();
+        self.for_each_pseudo_move(from, |to| possible_moves.push(to));
+        possible_moves.retain(|to| self.validate_move(from, *to));
         possible_moves
     }
 
-    fn get_pseudo_possible_moves(&self, from: Position) -> Vec<Position> {
-        let mut possible_moves: Vec<Position> = Vec::new();
+    pub fn legal_moves(&self) -> Vec<(Position, Position)> {
+        self.all_legal_moves_for(self.turn)
+    }
+
+    // Same moves as legal_moves, but generated lazily so that callers can stop early
+    pub fn legal_moves_iter(&self) -> LegalMoves<'_> {
+        LegalMoves::new(self)
+    }
 
-        let source_square = self.get_square(from);
+    // Number of legal moves of the side to move, the same as legal_moves().len(). Only one buffer is allocated for all pieces.
+    pub fn legal_moves_count(&self) -> usize {
+        if !self.can_make_moves() {
+            return 0;
+        }
 
-        if source_square.is_none() {
-            return possible_moves;
+        let mut game = self.clone();
+        let mut pseudo_possible_moves = Vec::new();
+        let mut count = 0;
+        for (from, _) in self.pieces_of(self.turn) {
+            pseudo_possible_moves.clear();
+            game.for_each_pseudo_move(from, |to| pseudo_possible_moves.push(to));
+            count += pseudo_possible_moves.iter().filter(|to| game.validate_move(from, **to)).count();
         }
-        let source_square = source_square.unwrap();
+        count
+    }
+
+    // Legal destinations of every piece of the side to move, leaving out pieces that can't move
+    pub fn legal_moves_by_source(&self) -> Vec<(Position, Vec<Position>)> {
+        if !self.can_make_moves() {
+            return Vec::new();
+        }
+
+        let mut game = self.clone();
+        self.pieces_of(self.turn)
+            .map(|(from, _)| (from, game.possible_moves(from)))
+            .filter(|(_, destinations)| !destinations.is_empty())
+            .collect()
+    }
+
+    // Legal moves of the given color, even if it isn't its turn
+    pub fn all_legal_moves_for(&self, color: Color) -> Vec<(Position, Position)> {
+        if !self.can_make_moves() {
+            return Vec::new();
+        }
+        self.clone().legal_moves_for(color)
+    }
+
+    // Only one buffer is allocated for the pseudo legal moves of all pieces, which matters for perft
+    fn legal_moves_for(&mut self, color: Color) -> Vec<(Position, Position)> {
+        let mut legal_moves: Vec<(Position, Position)> = Vec::new();
+        let mut pseudo_possible_moves: Vec<Position> = Vec::new();
+
+        for x in 0..=7 {
+            for y in 0..=7 {
+                let from = Position::new(x, y);
+
+                match self.get_square(from) {
+                    Some(piece) if piece.color == color => {}
+                    _ => continue,
+                }
+
+                pseudo_possible_moves.clear();
+                self.for_each_pseudo_move(from, |to| pseudo_possible_moves.push(to));
+                for &to in &pseudo_possible_moves {
+                    if self.validate_move(from, to) {
+                        legal_moves.push((from, to));
+                    }
+                }
+            }
+        }
+
+        legal_moves
+    }
+
+    // Counts the leaf nodes of the legal move tree (used to verify move generation)
+    pub fn perft(&self, depth: u32) -> u64 {
+        self.clone().count_leaf_nodes(depth)
+    }
+
+    fn count_leaf_nodes(&mut self, depth: u32) -> u64 {
+        if depth == 0 {
+            return 1;
+        }
+
+        let mut nodes = 0;
+        for (from, to) in self.legal_moves_for(self.turn) {
+            let undo = self.apply_move(from, to);
+
+            // every promotion choice counts as a separate move
+            if undo.moved.piece_type == PieceType::Pawn && (to.y == 0 || to.y == 7) && self.get_square(to).is_some() {
+                for piece_type in [PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen] {
+                    self.put_square(to, Some(Piece { piece_type, color: undo.moved.color }));
+                    nodes += self.count_leaf_nodes(depth - 1);
+                }
+            } else {
+                nodes += self.count_leaf_nodes(depth - 1);
+            }
+
+            self.undo_move(undo);
+        }
+
+        nodes
+    }
+
+    // Calls f with every pseudo legal destination of the piece at from, without allocating
+    fn for_each_pseudo_move(&self, from: Position, mut f: impl FnMut(Position)) {
+        let source_square = match self.get_square(from) {
+            Some(source_square) => source_square,
+            None => return,
+        };
 
         for x in 0..=7 {
             for y in 0..=7 {
@@ -531,105 +2210,220 @@ impl Game {
                 }
 
                 if self.pseudo_validate_move(from, pos) {
-                    possible_moves.push(pos)
+                    f(pos);
                 }
             }
         }
+    }
+}
 
-        possible_moves
+// Checks that a fen string is well formed and has one king per color, without loading it into a game
+pub fn validate_fen(fen: &str) -> Result<(), FenError> {
+    let parsed = parse_fen(fen, false)?;
+    for color in [Color::White, Color::Black] {
+        let king = Some(Piece { piece_type: PieceType::King, color });
+        if parsed.squares.iter().filter(|square| **square == king).count() != 1 {
+            return Err(FenError::WrongKingCount(color));
+        }
     }
+    Ok(())
+}
+
+struct ParsedFen {
+    squares: [Square; 8 * 8],
+    turn: Color,
+    en_passant_susceptible_pawn: Option<Position>,
+    castling_available: [bool; 4],
+    halfmove_clock: u32,
+    fullmove_number: u32,
 }
 
-fn check_game_state(game: &Game) -> GameState {
-    if game.moves_since_capture >= 50 {
-        // 50 move rule
-        return GameState::Draw;
+// Lenient parsing allows the clocks to be left out, defaulting them to 0 and 1
+fn parse_fen(fen: &str, lenient: bool) -> Result<ParsedFen, FenError> {
+    let segments: Vec<&str> = fen.split(" ").collect();
+
+    let allowed_segment_counts = if lenient { 4..=6 } else { 6..=6 };
+    if !allowed_segment_counts.contains(&segments.len()) {
+        return Err(FenError::WrongSegmentCount);
     }
 
-    // Find the kings
-    let mut white_king_pos = Position::new(0, 0);
-    let mut black_king_pos = Position::new(0, 0);
-    for x in 0..=7 {
-        for y in 0..=7 {
-            let pos = Position::new(x, y);
-            if let Some(square) = game.get_square(pos) {
-                if square.piece_type == PieceType::King {
-                    if square.color == Color::White {
-                        white_king_pos = pos;
-                    } else {
-                        black_king_pos = pos;
-                    }
-                }
-            }
-        }
+    let board_segments: Vec<&str> = segments[0].split("/").collect();
+    if board_segments.len() != 8 {
+        return Err(FenError::WrongRankCount);
     }
 
-    // Check for check
-    let in_check: Option<Color> = check_check(game, white_king_pos, black_king_pos);
+    // Parse segment 1: Board
+    let mut squares: [Square; 8 * 8] = [None; 8 * 8];
+    for (seg_index, seg) in board_segments.iter().enumerate() {
+        let mut filled_tiles = 0;
+
+        for chr in seg.chars() {
+            if chr.is_ascii_digit() {
+                filled_tiles += chr.to_digit(10).unwrap() as usize;
+                continue;
+            }
+
+            let color = if chr.is_uppercase() {
+                Color::White
+            } else {
+                Color::Black
+            };
+            let piece: PieceType = match PieceType::from_fen_char(chr) {
+                Some(piece) => piece,
+                None => return Err(FenError::BadPieceChar(chr)),
+            };
 
-    // Check if teams can move
-    let white_cant_move = cant_move(game, Color::White, white_king_pos, black_king_pos);
-    let black_cant_move = cant_move(game, Color::Black, white_king_pos, black_king_pos);
+            if filled_tiles >= 8 {
+                return Err(FenError::RankNotEightSquares);
+            }
 
-    // Check / checkmate / stalemate / normal
-    match in_check {
-        Some(in_check) => {
-            if in_check == Color::White && white_cant_move {
-                return GameState::Checkmate(Color::White);
+            squares[seg_index * 8 + filled_tiles] = Some(Piece {
+                piece_type: piece,
+                color,
+            });
+            filled_tiles += 1;
+        }
+
+        if filled_tiles != 8 {
+            return Err(FenError::RankNotEightSquares);
+        }
+    }
+
+    // Parse segment 2: Turn
+    let mut turn_chars = segments[1].chars();
+    let turn = match (turn_chars.next().and_then(Color::from_fen_char), turn_chars.next()) {
+        (Some(turn), None) => turn,
+        _ => return Err(FenError::BadActiveColor),
+    };
+
+    // segment 3: castling ability, each letter at most once
+    let mut castling_available = [false; 4];
+    if segments[2] != "-" {
+        if segments[2].is_empty() {
+            return Err(FenError::BadCastling);
+        }
+        for chr in segments[2].chars() {
+            let index = match chr {
+                'K' => 0,
+                'Q' => 1,
+                'k' => 2,
+                'q' => 3,
+                _ => return Err(FenError::BadCastling),
+            };
+            if castling_available[index] {
+                return Err(FenError::BadCastling);
             }
-            if in_check == Color::Black && black_cant_move {
-                return GameState::Checkmate(Color::Black);
+            castling_available[index] = true;
+        }
+    }
+
+    // segment 4: en passant target square (the pawn itself sits one square past the target)
+    let en_passant_susceptible_pawn = match segments[3] {
+        "-" => None,
+        target => {
+            let chars: Vec<char> = target.chars().collect();
+            match chars[..] {
+                [file @ 'a'..='h', '3'] => Some(Position::new(file as u8 - b'a', 3)),
+                [file @ 'a'..='h', '6'] => Some(Position::new(file as u8 - b'a', 4)),
+                _ => return Err(FenError::BadEnPassantSquare),
             }
+        }
+    };
+
+    // segment 5 & 6: halfmove clock and fullmove counter
+    let parse_clock = |segment: Option<&&str>, default: u32| match segment {
+        Some(segment) => segment.parse().map_err(|_| FenError::BadClock),
+        None => Ok(default),
+    };
+
+    Ok(ParsedFen {
+        squares,
+        turn,
+        en_passant_susceptible_pawn,
+        castling_available,
+        halfmove_clock: parse_clock(segments.get(4), 0)?,
+        fullmove_number: parse_clock(segments.get(5), 1)?,
+    })
+}
+
+fn check_game_state(game: &mut Game) -> GameState {
+    // Atomic, the side whose king is still standing has blown up the other one
+    if game.atomic {
+        match (game.get_king_position(Color::White), game.get_king_position(Color::Black)) {
+            (Some(_), None) => return GameState::Atomic(Color::White),
+            (None, Some(_)) => return GameState::Atomic(Color::Black),
+            _ => {}
+        }
+    }
 
-            GameState::Check(in_check)
+    // Three-check, the third check wins
+    if game.three_check {
+        if game.white_checks_given >= 3 {
+            return GameState::ThreeCheck(Color::White);
         }
-        None => {
-            if (white_cant_move && game.turn == Color::White)
-                || (black_cant_move && game.turn == Color::Black)
-            {
-                return GameState::Draw;
+        if game.black_checks_given >= 3 {
+            return GameState::ThreeCheck(Color::Black);
+        }
+    }
+
+    // King of the hill, reaching the center wins right away
+    if game.king_of_the_hill {
+        for color in [Color::White, Color::Black] {
+            if let Some(king_pos) = game.get_king_position(color) {
+                if (3..=4).contains(&king_pos.x) && (3..=4).contains(&king_pos.y) {
+                    return GameState::KingOfTheHill(color);
+                }
             }
-            GameState::Normal
         }
     }
+
+    // The seventy-five move rule and fivefold repetition, or the fifty-move rule and threefold repetition
+    // when those don't have to be claimed
+    let (halfmove_limit, repetition_limit) = if game.automatic_draws { (100, 3) } else { (150, 5) };
+    if game.fifty_move_rule && game.halfmove_clock >= halfmove_limit {
+        return GameState::FiftyMoveRule;
+    }
+    if game.repetition_count() >= repetition_limit {
+        return GameState::Repetition;
+    }
+
+    // Dead position, though a king can still reach the center and a lone bishop or knight can still give check
+    if !game.king_of_the_hill && !game.three_check && game.is_insufficient_material() {
+        return GameState::InsufficientMaterial;
+    }
+
+    // Check for check. Only the side to move can be in check, checkmated or stalemated: the other king being
+    // attacked is only possible in an illegal position (see validate_position), and is left as it is
+    let in_check = check_check(game).is_in_check(game.turn);
+    let turn_cant_move = cant_move(game, game.turn);
+
+    // Check / checkmate / stalemate / normal
+    match (in_check, turn_cant_move) {
+        (true, true) => GameState::Checkmate(game.turn),
+        (true, false) => GameState::Check(game.turn),
+        (false, true) => GameState::Stalemate(game.turn),
+        (false, false) => GameState::Normal,
+    }
 }
 
-fn cant_move(
-    game: &Game,
-    color: Color,
-    white_king_pos: Position,
-    black_king_pos: Position,
-) -> bool {
-    // check all pseudo possible moves, and for each of these check if it isnt check
+fn cant_move(game: &mut Game, color: Color) -> bool {
+    // check all pseudo possible moves, and for each of these check if it's legal
+    // (castling out of or through check doesn't count as a way out)
+    // The same buffer is reused for every piece so this doesn't allocate per square
+    let mut pseudo_possible_moves = Vec::new();
     for x in 0..=7 {
         for y in 0..=7 {
             let from = Position::new(x, y);
 
-            if let Some(square) = game.get_square(from) {
-                if square.color != color {
-                    continue;
-                }
+            match game.get_square(from) {
+                Some(square) if square.color == color => {}
+                _ => continue,
             }
 
-            let possible_moves = game.get_pseudo_possible_moves(from);
-            for to in possible_moves {
-                // update king positions if they were the ones who moved
-                let white_king_pos = if from == white_king_pos {
-                    to
-                } else {
-                    white_king_pos
-                };
-                let black_king_pos = if from == black_king_pos {
-                    to
-                } else {
-                    black_king_pos
-                };
-
-                // Clone the board and simulate the move
-                let mut new_game = game.clone();
-                new_game.set_square(to, new_game.get_square(from));
-                new_game.set_square(from, None);
-                if check_check(&new_game, white_king_pos, black_king_pos).is_none() {
+            pseudo_possible_moves.clear();
+            game.for_each_pseudo_move(from, |to| pseudo_possible_moves.push(to));
+            for &to in &pseudo_possible_moves {
+                if game.validate_move(from, to) {
                     return false;
                 }
             }
@@ -638,36 +2432,38 @@ fn cant_move(
     true
 }
 
-fn check_check(game: &Game, white_king_pos: Position, black_king_pos: Position) -> Option<Color> {
-    let mut black_check = false;
-    let mut white_check = false;
-
-    // Check if there are any possible moves that could capture a king
-    for x in 0..=7 {
-        for y in 0..=7 {
-            let pos = Position::new(x, y);
+struct CheckStatus {
+    white: bool,
+    black: bool,
+}
 
-            let possible_moves = game.get_pseudo_possible_moves(pos);
+impl CheckStatus {
+    fn is_in_check(&self, color: Color) -> bool {
+        match color {
+            Color::White => self.white,
+            Color::Black => self.black,
+        }
+    }
+}
 
-            for possible_move in possible_moves {
-                if possible_move == white_king_pos {
-                    white_check = true;
-                }
-                if possible_move == black_king_pos {
-                    black_check = true;
-                }
+fn check_check(game: &Game) -> CheckStatus {
+    // In atomic, touching kings can't be in check, since capturing one would blow up the other as well
+    if game.atomic {
+        if let (Some(white_king), Some(black_king)) = (game.get_king_position(Color::White), game.get_king_position(Color::Black)) {
+            if (white_king.x as i32 - black_king.x as i32).abs() <= 1 && (white_king.y as i32 - black_king.y as i32).abs() <= 1 {
+                return CheckStatus { white: false, black: false };
             }
         }
     }
 
-    // Logic for who's in check
-    if black_check && white_check {
-        Some(game.turn)
-    } else if black_check {
-        Some(Color::Black)
-    } else if white_check {
-        Some(Color::White)
-    } else {
-        None
+    // A king is in check if any opposing piece could capture it (a missing king is never in check)
+    let king_attacked = |color: Color| match game.get_king_position(color) {
+        Some(king_pos) => game.is_square_attacked(king_pos, !color),
+        None => false,
+    };
+
+    CheckStatus {
+        white: king_attacked(Color::White),
+        black: king_attacked(Color::Black),
     }
 }
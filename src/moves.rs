@@ -2,32 +2,19 @@ use std::cmp::max;
 
 use crate::{Color, Game, PieceType, Position, PositionBuilder};
 
+// Number of squares a sliding piece can move in the given direction, found with the occupancy bitboards
+// instead of walking square by square
 pub(crate) fn calc_max_move_len(
     game: &Game,
     moving_team: Color,
-    base: PositionBuilder,
+    from: Position,
     direction: (i32, i32),
     can_capture: bool,
 ) -> i32 {
-    let mut move_len = 0;
-    let mut builder = base;
-    loop {
-        builder = builder.walk(direction);
-        if let Some(pos) = builder.position {
-            let piece = game.get_square(pos);
-            if let Some(piece) = piece {
-                return if piece.color == moving_team {
-                    move_len
-                } else if can_capture {
-                    move_len + 1
-                } else {
-                    move_len
-                };
-            }
-            move_len += 1;
-            continue;
-        }
-        return move_len;
+    let move_len = game.occupancy.empty_ray_len(from, direction);
+    match game.occupancy.first_blocker(from, direction) {
+        Some(blocker) if can_capture && !game.occupancy.is_color(blocker, moving_team) => move_len + 1,
+        _ => move_len,
     }
 }
 
@@ -46,7 +33,7 @@ pub(crate) fn pseudo_validate_knight_move(game: &Game, from: Position, to: Posit
         base_builder.walk((-2, 1)).build(),
     ];
 
-    return valid_positions.iter().flatten().any(|pos| *pos == to);
+    valid_positions.iter().flatten().any(|pos| *pos == to)
 }
 
 pub(crate) fn pseudo_validate_pawn_move(game: &Game, from: Position, to: Position) -> bool {
@@ -143,7 +130,6 @@ pub(crate) fn pseudo_validate_pawn_move(game: &Game, from: Position, to: Positio
 
 pub(crate) fn pseudo_validate_rook_move(game: &Game, from: Position, to: Position) -> bool {
     let piece = game.get_square(from).unwrap();
-    let base_builder = PositionBuilder::set(from).color(piece.color);
 
     let x_diff = to.x as i32 - from.x as i32;
     let y_diff = to.y as i32 - from.y as i32;
@@ -158,7 +144,7 @@ pub(crate) fn pseudo_validate_rook_move(game: &Game, from: Position, to: Positio
         calc_max_move_len(
             game,
             piece.color,
-            base_builder,
+            from,
             (if x_diff > 0 { 1 } else { -1 }, 0),
             true,
         )
@@ -166,7 +152,7 @@ pub(crate) fn pseudo_validate_rook_move(game: &Game, from: Position, to: Positio
         calc_max_move_len(
             game,
             piece.color,
-            base_builder,
+            from,
             (0, if y_diff > 0 { 1 } else { -1 }),
             true,
         )
@@ -177,7 +163,6 @@ pub(crate) fn pseudo_validate_rook_move(game: &Game, from: Position, to: Positio
 
 pub(crate) fn pseudo_validate_bishop_move(game: &Game, from: Position, to: Position) -> bool {
     let piece = game.get_square(from).unwrap();
-    let base_builder = PositionBuilder::set(from).color(piece.color);
 
     let x_diff = to.x as i32 - from.x as i32;
     let y_diff = to.y as i32 - from.y as i32;
@@ -188,11 +173,22 @@ pub(crate) fn pseudo_validate_bishop_move(game: &Game, from: Position, to: Posit
 
     let x_mov = if x_diff > 0 { 1 } else { -1 };
     let y_mov = if y_diff > 0 { 1 } else { -1 };
-    let max_move_len = calc_max_move_len(game, piece.color, base_builder, (x_mov, y_mov), true);
+    let max_move_len = calc_max_move_len(game, piece.color, from, (x_mov, y_mov), true);
 
     x_diff.abs() <= max_move_len
 }
 
+// Checks that all squares between the king and the rook are empty
+// (queenside includes the b-file square even though the king never lands there)
+pub(crate) fn castling_path_clear(game: &Game, color: Color, kingside: bool) -> bool {
+    let offset_y = if color == Color::White { 0 } else { 7 };
+    let files: &[u8] = if kingside { &[5, 6] } else { &[1, 2, 3] };
+
+    files
+        .iter()
+        .all(|x| game.get_square(Position::new(*x, offset_y)).is_none())
+}
+
 pub(crate) fn pseudo_validate_king_move(game: &Game, from: Position, to: Position) -> bool {
     let piece = game.get_square(from).unwrap();
     let base_builder = PositionBuilder::set(from).color(piece.color);
@@ -212,7 +208,7 @@ pub(crate) fn pseudo_validate_king_move(game: &Game, from: Position, to: Positio
     
     if from == Position::new(4, offset_y) {
         // queenside
-        if to == Position::new(2, offset_y) && game.get_square(Position::new(3, offset_y)).is_none() && game.get_square(Position::new(2, offset_y)).is_none() && game.get_square(Position::new(1, offset_y)).is_none() {
+        if to == Position::new(2, offset_y) && castling_path_clear(game, piece.color, false) {
             if let Some(last_piece) = game.get_square(Position::new(0, offset_y)) {
                 if last_piece.piece_type == PieceType::Rook && ((piece.color == Color::White && game.white_castling_queenside_available) || (piece.color == Color::Black && game.black_castling_queenside_available)) {
                     return true;
@@ -220,7 +216,7 @@ pub(crate) fn pseudo_validate_king_move(game: &Game, from: Position, to: Positio
             } 
         }
         // kingside
-        if to == Position::new(6, offset_y) && game.get_square(Position::new(5, offset_y)).is_none() && game.get_square(Position::new(6, offset_y)).is_none() {
+        if to == Position::new(6, offset_y) && castling_path_clear(game, piece.color, true) {
             if let Some(last_piece) = game.get_square(Position::new(7, offset_y)) {
                 if last_piece.piece_type == PieceType::Rook && ((piece.color == Color::White && game.white_castling_kingside_available) || (piece.color == Color::Black && game.black_castling_kingside_available)) {
                     return true;
@@ -229,9 +225,66 @@ pub(crate) fn pseudo_validate_king_move(game: &Game, from: Position, to: Positio
         }
     }
 
-    return valid_positions.iter().flatten().any(|pos| *pos == to);
+    valid_positions.iter().flatten().any(|pos| *pos == to)
 }
 
 pub(crate) fn pseudo_validate_queen_move(game: &Game, from: Position, to: Position) -> bool {
     pseudo_validate_bishop_move(game, from, to) || pseudo_validate_rook_move(game, from, to)
 }
+
+// Lazy iterator over the legal moves (from, to) of the side to move, returned by Game::legal_moves_iter. A piece's
+// moves are only generated and checked once the iterator gets to it, in the same order as Game::legal_moves.
+pub struct LegalMoves<'a> {
+    game: &'a Game,
+    // Checking legality makes and takes back moves, so it needs a board of its own
+    scratch: Game,
+    next_square: u8,
+    from: Position,
+    destinations: Vec<Position>,
+    next_destination: usize,
+}
+
+impl<'a> LegalMoves<'a> {
+    pub(crate) fn new(game: &'a Game) -> Self {
+        LegalMoves {
+            game,
+            scratch: game.clone(),
+            // nothing to generate once the game is over or while a promotion is pending, like Game::legal_moves
+            next_square: if game.can_make_moves() { 0 } else { 8 * 8 },
+            from: Position::new(0, 0),
+            destinations: Vec::new(),
+            next_destination: 0,
+        }
+    }
+}
+
+impl Iterator for LegalMoves<'_> {
+    type Item = (Position, Position);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            while let Some(to) = self.destinations.get(self.next_destination).copied() {
+                self.next_destination += 1;
+                if self.scratch.validate_move(self.from, to) {
+                    return Some((self.from, to));
+                }
+            }
+
+            if self.next_square >= 8 * 8 {
+                return None;
+            }
+            let from = Position::new(self.next_square / 8, self.next_square % 8);
+            self.next_square += 1;
+            match self.game.get_square(from) {
+                Some(piece) if piece.color == self.game.turn => {}
+                _ => continue,
+            }
+
+            self.from = from;
+            self.destinations.clear();
+            self.next_destination = 0;
+            let destinations = &mut self.destinations;
+            self.game.for_each_pseudo_move(from, |to| destinations.push(to));
+        }
+    }
+}
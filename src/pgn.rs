@@ -0,0 +1,202 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{Color, FenError, Game, GameResult, PieceType, SanError};
+
+const STARTING_FEN: &str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+// The tags of the seven tag roster (the result tag is derived from the game state)
+#[derive(Clone, Debug, PartialEq)]
+pub struct PgnTags {
+    pub event: String,
+    pub site: String,
+    pub date: String,
+    pub round: String,
+    pub white: String,
+    pub black: String,
+}
+
+impl Default for PgnTags {
+    fn default() -> Self {
+        Self {
+            event: String::from("?"),
+            site: String::from("?"),
+            date: String::from("????.??.??"),
+            round: String::from("?"),
+            white: String::from("?"),
+            black: String::from("?"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PgnError {
+    BadFen(FenError),
+    BadMove {
+        move_number: u32,
+        san: String,
+        error: SanError,
+    },
+}
+
+impl fmt::Display for PgnError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PgnError::BadFen(error) => write!(f, "invalid fen tag: {}", error),
+            PgnError::BadMove { move_number, san, error } => {
+                write!(f, "move {} ({}): {}", move_number, san, error)
+            }
+        }
+    }
+}
+
+impl Error for PgnError {}
+
+impl Game {
+    pub fn to_pgn(&self) -> String {
+        self.to_pgn_with_tags(&PgnTags::default())
+    }
+
+    // Editing the board starts the move history over, so it always replays from where it started. Only an
+    // illegal move passed to make_move_trusted can break that, which panics here.
+    pub fn to_pgn_with_tags(&self, tags: &PgnTags) -> String {
+        let result = match self.get_game_result() {
+            GameResult::WhiteWins => "1-0",
+            GameResult::BlackWins => "0-1",
+            GameResult::Draw => "1/2-1/2",
+            GameResult::Ongoing => "*",
+        };
+
+        let mut pgn = String::new();
+        for (name, value) in [
+            ("Event", &tags.event),
+            ("Site", &tags.site),
+            ("Date", &tags.date),
+            ("Round", &tags.round),
+            ("White", &tags.white),
+            ("Black", &tags.black),
+        ] {
+            pgn.push_str(&format!("[{} \"{}\"]\n", name, value));
+        }
+        pgn.push_str(&format!("[Result \"{}\"]\n", result));
+
+        // Games that don't start from the starting position need to say where they start
+        let initial_fen = if self.move_history.is_empty() {
+            self.to_fen()
+        } else {
+            self.initial_fen.clone()
+        };
+        if initial_fen != STARTING_FEN {
+            pgn.push_str("[SetUp \"1\"]\n");
+            pgn.push_str(&format!("[FEN \"{}\"]\n", initial_fen));
+        }
+        pgn.push('\n');
+
+        // Replay the game from the start to get the notation of every move
+        let mut game = self.new_with_same_rules();
+        game.load_fen(&initial_fen).expect("exported fen should be valid");
+
+        let mut movetext: Vec<String> = Vec::new();
+        for (index, made_move) in self.move_history.iter().enumerate() {
+            if game.turn == Color::White {
+                movetext.push(format!("{}.", game.fullmove_number));
+            } else if index == 0 {
+                movetext.push(format!("{}...", game.fullmove_number));
+            }
+
+            let promotion = made_move.promotion.unwrap_or(PieceType::Queen);
+            let san = game
+                .move_to_san_with_promotion(made_move.from, made_move.to, promotion)
+                .expect("move history should replay from the initial fen");
+            movetext.push(san);
+            game.make_move_trusted(made_move);
+        }
+        movetext.push(String::from(result));
+
+        pgn.push_str(&movetext.join(" "));
+        pgn
+    }
+
+    // Loads a game from pgn, only the FEN tag is used and comments, variations and annotations are skipped.
+    // The game is left untouched if any move can't be made.
+    pub fn load_pgn(&mut self, pgn: &str) -> Result<(), PgnError> {
+        let mut game = self.new_with_same_rules();
+        let mut movetext = String::new();
+
+        for line in pgn.lines() {
+            let line = line.trim();
+            if let Some(tag) = line.strip_prefix('[').and_then(|tag| tag.strip_suffix(']')) {
+                if let Some(fen) = tag.strip_prefix("FEN ") {
+                    game.load_fen(fen.trim().trim_matches('"')).map_err(PgnError::BadFen)?;
+                }
+                continue;
+            }
+            // ';' comments run to the end of the line
+            movetext.push_str(line.split(';').next().unwrap_or(""));
+            movetext.push(' ');
+        }
+
+        for token in tokenize_movetext(&movetext) {
+            if ["1-0", "0-1", "1/2-1/2", "*"].contains(&token.as_str()) {
+                break;
+            }
+
+            if let Err(error) = game.make_move_san(&token) {
+                return Err(PgnError::BadMove {
+                    move_number: game.fullmove_number,
+                    san: token,
+                    error,
+                });
+            }
+        }
+
+        *self = game;
+        Ok(())
+    }
+}
+
+// Splits movetext into move and result tokens, dropping move numbers, {comments}, (variations) and $annotations
+fn tokenize_movetext(movetext: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    let mut in_comment = false;
+    let mut variation_depth = 0;
+
+    for chr in movetext.chars() {
+        if in_comment {
+            in_comment = chr != '}';
+            continue;
+        }
+        match chr {
+            '{' => in_comment = true,
+            '(' => variation_depth += 1,
+            ')' => variation_depth -= 1,
+            _ if variation_depth > 0 => {}
+            // Move numbers may be written right against the move, like "1.e4"
+            _ if chr.is_whitespace() || (chr == '.' && is_move_number(&current)) => {
+                push_token(&mut tokens, &mut current);
+                continue;
+            }
+            _ => {
+                current.push(chr);
+                continue;
+            }
+        }
+        push_token(&mut tokens, &mut current);
+    }
+    push_token(&mut tokens, &mut current);
+
+    tokens
+}
+
+fn push_token(tokens: &mut Vec<String>, current: &mut String) {
+    // "e.p." is only an annotation when written apart from the move
+    if !is_move_number(current) && !current.starts_with('$') && current != "e.p." {
+        tokens.push(current.clone());
+    }
+    current.clear();
+}
+
+fn is_move_number(token: &str) -> bool {
+    token.chars().all(|chr| chr.is_ascii_digit())
+}
@@ -0,0 +1,127 @@
+use std::fmt;
+
+use crate::{Color, Game, GameState, PieceType, Position, Square};
+
+// Which side of the board is drawn at the bottom
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Orientation {
+    White,
+    Black,
+}
+
+fn ascii_char(square: Square) -> char {
+    match square {
+        Some(piece) => piece.to_fen_char(),
+        None => '.',
+    }
+}
+
+fn unicode_char(square: Square) -> char {
+    match square {
+        Some(piece) => match (piece.color, piece.piece_type) {
+            (Color::White, PieceType::King) => '♔',
+            (Color::White, PieceType::Queen) => '♕',
+            (Color::White, PieceType::Rook) => '♖',
+            (Color::White, PieceType::Bishop) => '♗',
+            (Color::White, PieceType::Knight) => '♘',
+            (Color::White, PieceType::Pawn) => '♙',
+            (Color::Black, PieceType::King) => '♚',
+            (Color::Black, PieceType::Queen) => '♛',
+            (Color::Black, PieceType::Rook) => '♜',
+            (Color::Black, PieceType::Bishop) => '♝',
+            (Color::Black, PieceType::Knight) => '♞',
+            (Color::Black, PieceType::Pawn) => '♟',
+        },
+        None => '·',
+    }
+}
+
+fn piece_name(piece_type: PieceType) -> String {
+    piece_type.name().to_lowercase()
+}
+
+// Draws the board with rank labels to the left and file labels below.
+// Every square is a single char followed by a space so the columns stay aligned.
+fn draw_board(game: &Game, square_char: fn(Square) -> char, orientation: Orientation) -> String {
+    let flipped = orientation == Orientation::Black;
+    let ranks: Vec<u8> = if flipped { (0..=7).collect() } else { (0..=7).rev().collect() };
+    let files: Vec<u8> = if flipped { (0..=7).rev().collect() } else { (0..=7).collect() };
+
+    let mut board = String::new();
+    for y in &ranks {
+        board.push((b'1' + y) as char);
+        for x in &files {
+            board.push(' ');
+            board.push(square_char(game.get_square(Position::new(*x, *y))));
+        }
+        board.push('\n');
+    }
+
+    board.push(' ');
+    for x in &files {
+        board.push(' ');
+        board.push((b'a' + x) as char);
+    }
+    board
+}
+
+impl Game {
+    // Draws the board from white's perspective using unicode chess glyphs
+    pub fn to_unicode_board(&self) -> String {
+        self.to_unicode_board_oriented(Orientation::White)
+    }
+
+    // Draws the board from black's perspective using unicode chess glyphs
+    pub fn to_unicode_board_flipped(&self) -> String {
+        self.to_unicode_board_oriented(Orientation::Black)
+    }
+
+    pub fn to_unicode_board_oriented(&self, orientation: Orientation) -> String {
+        draw_board(self, unicode_char, orientation)
+    }
+
+    // A sentence describing the most recent move, e.g. "White knight captures on f6, check."
+    pub fn describe_last_move(&self) -> Option<String> {
+        let last_move = self.move_history().last()?;
+        // taken from the move, since the piece may have been promoted or blown up since
+        let piece = last_move.piece;
+
+        let mut description = if last_move.is_castle {
+            let side = if last_move.to.x > last_move.from.x { "kingside" } else { "queenside" };
+            format!("{:?} castles {}", piece.color, side)
+        } else {
+            let action = match (last_move.captured, last_move.is_en_passant) {
+                (Some(_), true) => "captures en passant on",
+                (Some(_), false) => "captures on",
+                (None, _) => "moves to",
+            };
+            format!("{:?} {} {} {}", piece.color, piece_name(piece.piece_type), action, last_move.to)
+        };
+
+        if let Some(promotion) = last_move.promotion {
+            description.push_str(&format!(" and promotes to {}", piece_name(promotion)));
+        }
+
+        match self.game_state() {
+            GameState::Check(_) => description.push_str(", check"),
+            GameState::Checkmate(_) => description.push_str(", checkmate"),
+            GameState::Stalemate(_) => description.push_str(", stalemate"),
+            _ => {}
+        }
+        description.push('.');
+        Some(description)
+    }
+
+    // Draws the board like Display does, but without the turn and game state and from either side
+    pub fn to_ascii_oriented(&self, orientation: Orientation) -> String {
+        draw_board(self, ascii_char, orientation)
+    }
+}
+
+// Prints the board from white's perspective, followed by whose turn it is and the game state
+impl fmt::Display for Game {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}", self.to_ascii_oriented(Orientation::White))?;
+        write!(f, "{:?} to move, {:?}", self.turn, self.game_state)
+    }
+}
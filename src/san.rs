@@ -0,0 +1,231 @@
+use std::error::Error;
+use std::fmt;
+
+use crate::{Color, Game, GameState, Move, MoveResult, PieceType, Position};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SanError {
+    Invalid,
+    Illegal,
+    Ambiguous,
+}
+
+impl fmt::Display for SanError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SanError::Invalid => write!(f, "malformed algebraic notation"),
+            SanError::Illegal => write!(f, "no legal move matches the notation"),
+            SanError::Ambiguous => write!(f, "more than one legal move matches the notation"),
+        }
+    }
+}
+
+impl Error for SanError {}
+
+impl Game {
+    // Standard algebraic notation for a move, must be called before the move is made.
+    // Promotions are assumed to be to a queen, use move_to_san_with_promotion to pick the piece.
+    pub fn move_to_san(&self, from: Position, to: Position) -> Option<String> {
+        self.move_to_san_with_promotion(from, to, PieceType::Queen)
+    }
+
+    pub fn move_to_san_with_promotion(
+        &self,
+        from: Position,
+        to: Position,
+        promotion: PieceType,
+    ) -> Option<String> {
+        let piece = self.get_square(from)?;
+
+        // Play the move on a copy to make sure it's legal and to find out if it checks
+        let mut new_game = self.simulate_move(from, to)?;
+        let promotes = matches!(new_game.game_state, GameState::AwaitingPromotion(_));
+        if promotes && new_game.promote(promotion) == MoveResult::Disallowed {
+            return None;
+        }
+
+        let mut san = String::new();
+        let x_diff = to.x as i32 - from.x as i32;
+
+        if piece.piece_type == PieceType::King && x_diff.abs() == 2 {
+            san.push_str(if x_diff > 0 { "O-O" } else { "O-O-O" });
+        } else {
+            // a pawn moving diagonally always captures (en passant lands on an empty square)
+            let is_capture = self.get_square(to).is_some()
+                || (piece.piece_type == PieceType::Pawn && x_diff != 0);
+
+            if piece.piece_type == PieceType::Pawn {
+                if is_capture {
+                    san.push((b'a' + from.x) as char);
+                }
+            } else {
+                san.push(piece.piece_type.to_fen_char().to_ascii_uppercase());
+
+                // Disambiguate if other pieces of the same type could also move there
+                let others = self.other_pieces_reaching(from, to);
+                if !others.is_empty() {
+                    let same_file = others.iter().any(|pos| pos.x == from.x);
+                    let same_rank = others.iter().any(|pos| pos.y == from.y);
+                    if !same_file {
+                        san.push((b'a' + from.x) as char);
+                    } else if !same_rank {
+                        san.push((b'1' + from.y) as char);
+                    } else {
+                        san.push_str(&from.to_string());
+                    }
+                }
+            }
+
+            if is_capture {
+                san.push('x');
+            }
+            san.push_str(&to.to_string());
+
+            if promotes {
+                san.push('=');
+                san.push(promotion.to_fen_char().to_ascii_uppercase());
+            }
+        }
+
+        match new_game.game_state {
+            GameState::Checkmate(color) if color != piece.color => san.push('#'),
+            GameState::Check(color) if color != piece.color => san.push('+'),
+            _ => {}
+        }
+
+        Some(san)
+    }
+
+    // Resolves standard algebraic notation (e.g. "Nbd2", "O-O-O", "exd6 e.p.", "fxg1=N") against the legal moves and makes the move
+    pub fn make_move_san(&mut self, san: &str) -> Result<Move, SanError> {
+        let (from, to, promotion) = self.resolve_san(san)?;
+
+        let mut made_move = self.make_move(from, to).map_err(|_| SanError::Illegal)?;
+        if let Some(promotion) = promotion {
+            // only recorded if it actually happened, so that the result matches the move history
+            if self.promote(promotion) != MoveResult::Allowed {
+                return Err(SanError::Illegal);
+            }
+            made_move.promotion = Some(promotion);
+        }
+
+        Ok(made_move)
+    }
+
+    fn resolve_san(&self, san: &str) -> Result<(Position, Position, Option<PieceType>), SanError> {
+        // Strip annotations that don't affect which move is meant
+        let mut san = san.trim();
+        for suffix in ["e.p.", "ep"] {
+            san = san.strip_suffix(suffix).unwrap_or(san).trim_end();
+        }
+        let san = san.trim_end_matches(['+', '#', '!', '?']);
+
+        // Castling
+        let back_rank = if self.turn == Color::White { 0 } else { 7 };
+        let castling_target = match san {
+            "O-O" | "0-0" => Some(6),
+            "O-O-O" | "0-0-0" => Some(2),
+            _ => None,
+        };
+        if let Some(target_x) = castling_target {
+            let from = Position::new(4, back_rank);
+            let to = Position::new(target_x, back_rank);
+            return match self.get_square(from) {
+                Some(piece) if piece.piece_type == PieceType::King && self.get_possible_moves(from).contains(&to) => {
+                    Ok((from, to, None))
+                }
+                _ => Err(SanError::Illegal),
+            };
+        }
+
+        let mut chars: Vec<char> = san.chars().filter(|chr| *chr != 'x' && *chr != '=').collect();
+
+        // Promotion piece at the end, e.g. "e8=Q" or "e8Q"
+        let mut promotion = None;
+        if let Some(last) = chars.last() {
+            if last.is_ascii_uppercase() {
+                promotion = Some(PieceType::from_fen_char(*last).ok_or(SanError::Invalid)?);
+                chars.pop();
+            }
+        }
+
+        // Moving piece at the start, pawns have no letter
+        let piece_type = match chars.first() {
+            Some(chr) if chr.is_ascii_uppercase() => {
+                let piece_type = PieceType::from_fen_char(*chr).ok_or(SanError::Invalid)?;
+                chars.remove(0);
+                piece_type
+            }
+            _ => PieceType::Pawn,
+        };
+
+        // Destination square is always the last two chars, anything before it disambiguates
+        if chars.len() < 2 {
+            return Err(SanError::Invalid);
+        }
+        let destination: String = chars[chars.len() - 2..].iter().collect();
+        let to = Position::from_string(&destination).ok_or(SanError::Invalid)?;
+
+        let mut from_file = None;
+        let mut from_rank = None;
+        for chr in &chars[..chars.len() - 2] {
+            match chr {
+                'a'..='h' => from_file = Some(*chr as u8 - b'a'),
+                '1'..='8' => from_rank = Some(*chr as u8 - b'1'),
+                _ => return Err(SanError::Invalid),
+            }
+        }
+
+        // Pawns without a file given are pushes
+        if piece_type == PieceType::Pawn && from_file.is_none() {
+            from_file = Some(to.x);
+        }
+
+        let candidates: Vec<(Position, Position)> = self
+            .legal_moves()
+            .into_iter()
+            .filter(|(from, target)| {
+                *target == to
+                    && self.get_square(*from).map(|piece| piece.piece_type) == Some(piece_type)
+                    && from_file.is_none_or(|x| from.x == x)
+                    && from_rank.is_none_or(|y| from.y == y)
+            })
+            .collect();
+
+        let (from, to) = match candidates[..] {
+            [] => return Err(SanError::Illegal),
+            [candidate] => candidate,
+            _ => return Err(SanError::Ambiguous),
+        };
+
+        // The promotion piece has to be given exactly when a pawn reaches the last rank (and isn't blown up there)
+        let promotes = self.move_promotes(from, to);
+        match (promotes, promotion) {
+            (true, Some(PieceType::Pawn | PieceType::King)) => Err(SanError::Illegal),
+            (true, Some(_)) | (false, None) => Ok((from, to, promotion)),
+            _ => Err(SanError::Invalid),
+        }
+    }
+
+    // Positions of the other pieces of the same type and color as the one at from that can legally move to to
+    fn other_pieces_reaching(&self, from: Position, to: Position) -> Vec<Position> {
+        let piece = match self.get_square(from) {
+            Some(piece) => piece,
+            None => return Vec::new(),
+        };
+
+        let mut positions = Vec::new();
+        for x in 0..=7 {
+            for y in 0..=7 {
+                let pos = Position::new(x, y);
+                if pos == from || self.get_square(pos) != Some(piece) {
+                    continue;
+                }
+                if self.get_possible_moves(pos).contains(&to) {
+                    positions.push(pos);
+                }
+            }
+        }
+        positions
+    }
+}
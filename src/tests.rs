@@ -2,22 +2,23 @@
 mod chess_tests {
     use std::collections::HashSet;
 
-    use crate::{Color, Game, GameState, MoveResult, PieceType, Position};
+    use crate::bitboard::Occupancy;
+    use crate::{check_check, validate_fen, BytesError, CastlingRights, CastlingSide, Color, DrawClaim, FenError, Game, GameBuilder, GameResult, GameState, LegalMoves, MoveError, MoveHint, MoveLegality, MoveResult, Orientation, PgnError, PgnTags, Piece, PieceType, Position, PositionError, PositionLegalityError, PositionParseError, SanError, SetupError, Square, Termination};
 
     #[test]
     fn checkmate_tests() {
         let mut game = Game::new();
 
         // scenario 1
-        game.load_fen("8/4K3/8/2p5/8/8/1R6/R3k3 b KQkq - 0 1");
+        game.load_fen("8/4K3/8/2p5/8/8/1R6/R3k3 b KQkq - 0 1").unwrap();
         assert_eq!(game.game_state, GameState::Checkmate(Color::Black));
 
         // scenario 2
-        game.load_fen("7k/5N1p/8/8/8/8/8/2K3R1 b KQkq - 0 1");
+        game.load_fen("7k/5N1p/8/8/8/8/8/2K3R1 b KQkq - 0 1").unwrap();
         assert_eq!(game.game_state, GameState::Checkmate(Color::Black));
 
         // scenario 3
-        game.load_fen("6k1/8/8/8/8/5pP1/5PqP/6K1 w KQkq - 0 1");
+        game.load_fen("6k1/8/8/8/8/5pP1/5PqP/6K1 w KQkq - 0 1").unwrap();
         assert_eq!(game.game_state, GameState::Checkmate(Color::White));
     }
 
@@ -26,19 +27,19 @@ mod chess_tests {
         let mut game = Game::new();
 
         // scenario 1
-        game.load_fen("rnbqkbnr/pppppppp/8/3P4/8/8/PP2PPPP/RNPQKBNR b KQkq - 0 1");
-        game.make_move(Position::new(2, 6), Position::new(2, 4)); 
+        game.load_fen("rnbqkbnr/pppppppp/8/3P4/8/8/PP2PPPP/RNPQKBNR b KQkq - 0 1").unwrap();
+        game.make_move(Position::new(2, 6), Position::new(2, 4)).unwrap();
         let res = game.make_move(Position::new(3, 4), Position::new(2, 5));
 
-        assert_eq!(res, MoveResult::Allowed);
+        assert!(res.is_ok());
         assert!(game.get_square(Position::new(2, 4)).is_none());
 
         // scenario 2
-        game.load_fen("rnbqkbnr/pppppp1p/8/8/6p1/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
-        game.make_move(Position::new(5, 1), Position::new(5, 3));
+        game.load_fen("rnbqkbnr/pppppp1p/8/8/6p1/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        game.make_move(Position::new(5, 1), Position::new(5, 3)).unwrap();
         let res = game.make_move(Position::new(6, 3), Position::new(5, 2));
         
-        assert_eq!(res, MoveResult::Allowed);
+        assert!(res.is_ok());
         assert!(game.get_square(Position::new(5, 3)).is_none());
     }
 
@@ -47,24 +48,167 @@ mod chess_tests {
         let mut game = Game::new();
 
         // scenario 1
-        game.load_fen("k7/8/1Q6/8/8/8/8/K7 b KQkq - 0 1");
-        assert_eq!(game.game_state, GameState::Draw);
+        game.load_fen("k7/8/1Q6/8/8/8/8/K7 b KQkq - 0 1").unwrap();
+        assert_eq!(game.game_state, GameState::Stalemate(Color::Black));
+        assert_eq!(game.get_game_result(), GameResult::Draw);
 
         // scenario 2
-        game.load_fen("k7/5b2/4r3/3K4/2r5/1b6/8/8 w KQkq - 0 1");
-        assert_eq!(game.game_state, GameState::Draw);
+        game.load_fen("k7/5b2/4r3/3K4/2r5/1b6/8/8 w KQkq - 0 1").unwrap();
+        assert_eq!(game.game_state, GameState::Stalemate(Color::White));
         // scenario 3
 
-        game.load_fen("k7/5b2/4r3/3K4/2r5/1b6/8/8 b KQkq - 0 1");
+        game.load_fen("k7/5b2/4r3/3K4/2r5/1b6/8/8 b KQkq - 0 1").unwrap();
+        assert_eq!(game.game_state, GameState::Normal);
+
+        // scenario 4: the side that isn't to move has no legal moves, which doesn't matter yet
+        game.load_fen("k7/2Q5/1K6/8/8/8/8/8 w - - 0 1").unwrap();
+        assert!(game.all_legal_moves_for(Color::Black).is_empty());
+        assert_eq!(game.game_state, GameState::Normal);
+    }
+
+    #[test]
+    fn last_move_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.last_move, None);
+
+        // scenario 1: a normal move, and failed moves don't change it
+        game.make_move_san("e4").unwrap();
+        let e2_e4 = Some((Position::from_string("e2").unwrap(), Position::from_string("e4").unwrap()));
+        assert_eq!(game.last_move, e2_e4);
+        game.make_move(Position::from_string("e7").unwrap(), Position::from_string("e3").unwrap()).unwrap_err();
+        assert_eq!(game.last_move, e2_e4);
+
+        // looking for legal moves makes and takes back moves, which shouldn't change it
+        game.legal_moves();
+        assert_eq!(game.last_move, e2_e4);
+
+        // scenario 2: castling shows the king's move
+        game.load_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert_eq!(game.last_move, None);
+        game.make_move_san("O-O").unwrap();
+        assert_eq!(game.last_move, Some((Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap())));
+    }
+
+    #[test]
+    fn game_builder_tests() {
+        // scenario 1: the defaults match Game::new
+        let game = GameBuilder::new().build().unwrap();
+        assert!(game == Game::new());
+        assert!(game.fifty_move_rule() && game.stalemate_is_draw());
+        assert!(!game.king_of_the_hill() && !game.three_check() && !game.atomic());
+
+        // scenario 2: no fifty-move rule
+        let mut game = GameBuilder::new()
+            .fifty_move_rule(false)
+            .starting_fen("k7/8/8/8/8/8/8/K6R w - - 99 80")
+            .build()
+            .unwrap();
+        assert!(!game.fifty_move_rule());
+        game.make_move_san("Rh2").unwrap();
+        assert_eq!(game.halfmove_clock, 100);
+        assert_eq!(game.game_state, GameState::Normal);
+        assert_eq!(game.can_claim_draw(), None);
+        game.load_fen("k7/8/8/8/8/8/8/K6R w - - 150 80").unwrap();
+        assert_eq!(game.game_state, GameState::Normal);
+
+        // scenario 3: stalemate loses
+        let game = GameBuilder::new()
+            .stalemate_is_draw(false)
+            .starting_fen("k7/8/1Q6/8/8/8/8/K7 b - - 0 1")
+            .build()
+            .unwrap();
+        assert_eq!(game.game_state, GameState::Stalemate(Color::Black));
+        assert_eq!(game.get_game_result(), GameResult::WhiteWins);
+
+        // scenario 4: bad fen
+        assert_eq!(GameBuilder::new().starting_fen("8/8 w - - 0 1").build().err(), Some(FenError::WrongRankCount));
+    }
+
+    #[test]
+    fn three_check_tests() {
+        let mut game = GameBuilder::new().three_check(true).build().unwrap();
+
+        // white gives three checks with the bishop and queen
+        let moves = ["e4", "f6", "Bc4", "d5", "Bxd5", "Nc6", "Bf7+", "Kxf7", "Qh5+", "Ke6", "Qe5+"];
+        for (i, san) in moves.iter().enumerate() {
+            game.make_move_san(san).unwrap();
+            if i < moves.len() - 1 {
+                assert!(!matches!(game.game_state, GameState::ThreeCheck(_)), "{}", san);
+            }
+        }
+        assert_eq!(game.white_checks_given, 3);
+        assert_eq!(game.black_checks_given, 0);
+        assert_eq!(game.game_state, GameState::ThreeCheck(Color::White));
+        assert_eq!(game.get_game_result(), GameResult::WhiteWins);
+        assert!(game.make_move_san("Kd6").is_err());
+
+        // loading a fen resets the counters
+        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(game.white_checks_given, 0);
+        assert_eq!(game.game_state, GameState::Normal);
+
+        // checks are still counted without the variant, but don't end the game
+        game = Game::new();
+        for san in moves {
+            game.make_move_san(san).unwrap();
+        }
+        assert_eq!(game.white_checks_given, 3);
+        assert_eq!(game.game_state, GameState::Check(Color::Black));
+
+        // a check given by promoting is counted once
+        game.load_fen("8/1P2k3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move_san("b8=Q").unwrap();
+        assert_eq!(game.white_checks_given, 0);
+        game.load_fen("7k/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move_san("b8=Q").unwrap();
+        assert_eq!(game.white_checks_given, 1);
+    }
+
+    #[test]
+    fn king_of_the_hill_tests() {
+        let mut game = GameBuilder::new().king_of_the_hill(true).build().unwrap();
+        assert!(!Game::new().king_of_the_hill);
+
+        // walk the white king to e4
+        for san in ["e3", "a6", "Ke2", "a5", "Kd3", "a4"] {
+            game.make_move_san(san).unwrap();
+            assert_eq!(game.game_state, GameState::Normal);
+        }
+        game.make_move_san("Ke4").unwrap();
+        assert_eq!(game.game_state, GameState::KingOfTheHill(Color::White));
+        assert_eq!(game.get_game_result(), GameResult::WhiteWins);
+        assert!(game.make_move_san("a3").is_err());
+
+        // without the variant it's just a normal move
+        game = Game::new();
+        for san in ["e3", "a6", "Ke2", "a5", "Kd3", "a4", "Ke4"] {
+            game.make_move_san(san).unwrap();
+        }
         assert_eq!(game.game_state, GameState::Normal);
     }
 
+    #[test]
+    fn no_moves_after_draw_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: stalemate
+        game.load_fen("k7/8/1Q6/8/8/8/8/K7 b KQkq - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("a8").unwrap(), Position::from_string("b8").unwrap()).is_err());
+        assert_eq!(game.make_move(Position::from_string("b6").unwrap(), Position::from_string("b7").unwrap()), Err(MoveError::GameOver));
+
+        // scenario 2: seventy-five move rule, where the move would otherwise be legal
+        game.load_fen("k7/8/8/8/8/8/8/K6R w - - 150 80").unwrap();
+        assert_eq!(game.game_state, GameState::FiftyMoveRule);
+        assert_eq!(game.make_move(Position::from_string("h1").unwrap(), Position::from_string("h2").unwrap()), Err(MoveError::GameOver));
+        assert_eq!(game.get_square(Position::from_string("h1").unwrap()), Some(Piece { piece_type: PieceType::Rook, color: Color::White }));
+    }
+
     #[test]
     fn check_possible_moves_test() {
         let mut game = Game::new();
 
         // scenario 1
-        game.load_fen("1r6/8/4k3/8/2K5/2P5/8/8 w KQkq - 0 1");
+        game.load_fen("1r6/8/4k3/8/2K5/2P5/8/8 w KQkq - 0 1").unwrap();
         let correct_possible_moves = vec![
             Position::new(2, 4),
             Position::new(3, 3),
@@ -77,13 +221,78 @@ mod chess_tests {
         ));
 
         // scenario 2
-        game.load_fen("8/8/8/4p1b1/5P2/8/8/2K5 w KQkq - 0 1");
+        game.load_fen("8/8/8/4p1b1/5P2/8/8/2K5 w KQkq - 0 1").unwrap();
         let correct_possible_moves = vec![Position::new(6, 4)];
         let possible_moves = game.get_possible_moves(Position::new(5, 3));
         assert!(no_order_iters_eq(
             possible_moves.into_iter(),
             correct_possible_moves.into_iter()
         ));
+
+        // scenario 3: empty squares and pieces of the side not to move have no moves
+        assert!(game.get_possible_moves(Position::new(0, 0)).is_empty());
+        assert!(game.get_possible_moves(Position::new(4, 4)).is_empty());
+        assert!(game.get_possible_moves(Position::new(6, 4)).is_empty());
+    }
+
+    #[test]
+    fn legal_moves_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position
+        assert_eq!(game.legal_moves().len(), 20);
+
+        // scenario 2: castling and en passant are included
+        game.load_fen("4k3/8/8/3pP3/8/8/8/4K2R w K d6 0 1").unwrap();
+        let legal_moves = game.legal_moves();
+        assert!(legal_moves.contains(&(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap())));
+        assert!(legal_moves.contains(&(Position::from_string("e5").unwrap(), Position::from_string("d6").unwrap())));
+
+        // scenario 3: checkmate
+        game.load_fen("7k/5N1p/8/8/8/8/8/2K3R1 b KQkq - 0 1").unwrap();
+        assert!(game.legal_moves().is_empty());
+
+        // scenario 4: stalemate
+        game.load_fen("k7/8/1Q6/8/8/8/8/K7 b KQkq - 0 1").unwrap();
+        assert!(game.legal_moves().is_empty());
+    }
+
+    #[test]
+    fn all_legal_moves_for_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: same as legal_moves for the side to move
+        assert_eq!(game.all_legal_moves_for(Color::White), game.legal_moves());
+        assert_eq!(game.all_legal_moves_for(Color::Black).len(), 20);
+
+        // scenario 2: black's pinned knight can't move even though it's white's turn
+        game.load_fen("4k3/4n3/8/8/8/8/8/4RK2 w - - 0 1").unwrap();
+        assert_eq!(game.all_legal_moves_for(Color::White), game.legal_moves());
+        let black_moves = game.all_legal_moves_for(Color::Black);
+        assert!(black_moves.iter().all(|(from, _)| *from == Position::from_string("e8").unwrap()));
+        assert_eq!(black_moves.len(), 4);
+    }
+
+    #[test]
+    fn perft_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position
+        assert_eq!(game.perft(1), 20);
+        assert_eq!(game.perft(2), 400);
+        assert_eq!(game.perft(3), 8902);
+
+        // moves are made and taken back on a single board, so deeper searches stay fast
+        assert_eq!(game.perft(4), 197281);
+
+        // scenario 2: castling, promotions and en passant ("kiwipete")
+        game.load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.perft(1), 48);
+        assert_eq!(game.perft(2), 2039);
+
+        // scenario 3: en passant exposing the king along the rank
+        game.load_fen("8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1").unwrap();
+        assert_eq!(game.perft(3), 2812);
     }
 
     #[test]
@@ -91,22 +300,2226 @@ mod chess_tests {
         let mut game = Game::new();
         
         // scenario 1
-        game.load_fen("rn1qkbnr/pppppppp/8/8/b7/8/PP1PPPPP/R3KBNR w KQkq - 0 1");
-        assert!(game.make_move(Position::from_string("e1"), Position::from_string("c1")) == MoveResult::Disallowed);
+        game.load_fen("rn1qkbnr/pppppppp/8/8/b7/8/PP1PPPPP/R3KBNR w KQkq - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("c1").unwrap()).is_err());
 
         // scenario 2
-        game.load_fen("rn1qkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3KBNR w KQkq - 0 1");
-        assert!(game.make_move(Position::from_string("e1"), Position::from_string("c1")) == MoveResult::Allowed);
+        game.load_fen("rn1qkbnr/pppppppp/8/8/8/8/PPPPPPPP/R3KBNR w KQkq - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("c1").unwrap()).is_ok());
         
-        let square = game.get_square(Position::from_string("d1"));
+        let square = game.get_square(Position::from_string("d1").unwrap());
         assert!(square.is_some());
         if let Some(square) = square {
             assert!(square.piece_type == PieceType::Rook);
         }
 
         // scenario 3
-        game.load_fen("rn1qkbn1/ppppppp1/6r1/8/8/8/PPPPP2P/RNBQK2R w KQq - 0 1");
-        assert!(game.make_move(Position::from_string("e1"), Position::from_string("g1")) == MoveResult::Disallowed);
+        game.load_fen("rn1qkbn1/ppppppp1/6r1/8/8/8/PPPPP2P/RNBQK2R w KQq - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap()).is_err());
+
+        // scenario 4: the h1 rook gets captured and replaced by another rook
+        game.load_fen("4k3/8/2b5/7R/8/8/8/4K2R b K - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("c6").unwrap(), Position::from_string("h1").unwrap()).is_ok());
+        assert!(!game.white_castling_kingside_available);
+        assert!(game.make_move(Position::from_string("h5").unwrap(), Position::from_string("h1").unwrap()).is_ok());
+        assert!(game.make_move(Position::from_string("e8").unwrap(), Position::from_string("d8").unwrap()).is_ok());
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap()).is_err());
+
+        // scenario 5: can't castle out of check
+        game.load_fen("4r1k1/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap()).is_err());
+
+        // scenario 6: can't castle through check
+        game.load_fen("4k3/8/8/8/8/7b/8/4K2R w K - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap()).is_err());
+        assert!(!game.get_possible_moves(Position::from_string("e1").unwrap()).contains(&Position::from_string("g1").unwrap()));
+
+        // scenario 7: a knight on b1 blocks queenside castling
+        game.load_fen("4k3/8/8/8/8/8/8/RN2K3 w Q - 0 1").unwrap();
+        assert!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("c1").unwrap()).is_err());
+    }
+
+    #[test]
+    fn position_tests() {
+        assert_eq!(Position::try_new(3, 7), Ok(Position::new(3, 7)));
+        assert_eq!(Position::try_new(8, 0), Err(PositionError::OutOfBounds));
+        assert_eq!(Position::try_new(0, 200), Err(PositionError::OutOfBounds));
+    }
+
+    #[test]
+    fn algebraic_notation_tests() {
+        assert_eq!(Position::from_string("a1"), Some(Position::new(0, 0)));
+        assert_eq!(Position::from_string("E4"), Some(Position::new(4, 3)));
+        assert_eq!(Position::from_string("h8"), Some(Position::new(7, 7)));
+        assert_eq!(Position::from_string("i1"), None);
+        assert_eq!(Position::from_string("e9"), None);
+        assert_eq!(Position::from_string("e44"), None);
+        assert_eq!(Position::from_string(""), None);
+
+        assert_eq!(Position::new(4, 3).to_string(), "e4");
+        assert_eq!(Position::new(7, 7).to_string(), "h8");
+    }
+
+    #[test]
+    fn position_tuple_tests() {
+        assert_eq!(Position::from((4, 1)), Position::new(4, 1));
+        let pos: Position = (7, 7).into();
+        assert_eq!(pos.to_string(), "h8");
+        assert_eq!(<(u8, u8)>::from(Position::new(2, 5)), (2, 5));
+    }
+
+    #[test]
+    #[should_panic]
+    fn position_tuple_out_of_bounds_test() {
+        let _ = Position::from((8, 0));
+    }
+
+    #[test]
+    fn position_parse_tests() {
+        assert_eq!("e4".parse::<Position>(), Ok(Position::new(4, 3)));
+        assert_eq!("A1".parse::<Position>(), Ok(Position::new(0, 0)));
+        assert_eq!(Position::try_from("h8"), Ok(Position::new(7, 7)));
+
+        assert_eq!("e9".parse::<Position>(), Err(PositionParseError::BadRank('9')));
+        assert_eq!("i1".parse::<Position>(), Err(PositionParseError::BadFile('i')));
+        assert_eq!("".parse::<Position>(), Err(PositionParseError::WrongLength));
+        assert_eq!(Position::try_from("e44"), Err(PositionParseError::WrongLength));
+    }
+
+    #[test]
+    fn position_index_tests() {
+        assert_eq!(Position::from_string("a8").unwrap().to_index(), 0);
+        assert_eq!(Position::from_string("h8").unwrap().to_index(), 7);
+        assert_eq!(Position::from_string("a1").unwrap().to_index(), 56);
+        assert_eq!(Position::from_string("h1").unwrap().to_index(), 63);
+
+        assert_eq!(Position::from_index(0), Position::from_string("a8"));
+        assert_eq!(Position::from_index(36), Position::from_string("e4"));
+        assert_eq!(Position::from_index(64), None);
+
+        // round trip, and the index matches the board's own layout
+        let game = Game::new();
+        for i in 0..64 {
+            let pos = Position::from_index(i).unwrap();
+            assert_eq!(pos.to_index(), i as usize);
+            assert_eq!(game.squares[pos.to_index()], game.get_square(pos));
+        }
+    }
+
+    #[test]
+    fn fen_char_tests() {
+        assert_eq!(PieceType::Knight.to_fen_char(), 'n');
+        assert_eq!(Piece { piece_type: PieceType::Knight, color: Color::White }.to_fen_char(), 'N');
+        assert_eq!(Piece { piece_type: PieceType::Queen, color: Color::Black }.to_fen_char(), 'q');
+
+        assert_eq!(PieceType::from_fen_char('k'), Some(PieceType::King));
+        assert_eq!(PieceType::from_fen_char('R'), Some(PieceType::Rook));
+        assert_eq!(PieceType::from_fen_char('x'), None);
+    }
+
+    #[test]
+    fn color_and_piece_type_display_tests() {
+        assert_eq!(Color::White.to_fen_char(), 'w');
+        assert_eq!(Color::Black.to_fen_char(), 'b');
+        assert_eq!(Color::from_fen_char('b'), Some(Color::Black));
+        assert_eq!(Color::from_fen_char('W'), None);
+
+        assert_eq!(Color::White.opponent(), Color::Black);
+        assert_eq!(Color::Black.opponent(), !Color::Black);
+
+        assert_eq!(Color::White.to_string(), "w");
+        assert_eq!(format!("{}{}", Color::Black, PieceType::Knight), "bn");
+        assert_eq!(PieceType::Queen.to_string(), "q");
+    }
+
+    #[test]
+    fn fen_export_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position
+        assert_eq!(
+            game.to_fen(),
+            "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"
+        );
+
+        // scenario 2: no castling rights left
+        game.load_fen("4k3/8/8/8/8/8/8/4K3 b - - 12 1").unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4K3 b - - 12 1");
+
+        // scenario 3: round trip
+        game.load_fen("rnbqkbnr/pppppp1p/8/8/6p1/8/PPPPPPPP/RNBQKBNR w Kq - 0 1").unwrap();
+        game.make_move(Position::new(5, 1), Position::new(5, 3)).unwrap();
+        let mut loaded_game = Game::new();
+        loaded_game.load_fen(&game.to_fen()).unwrap();
+
+        assert_eq!(loaded_game.to_fen(), game.to_fen());
+        assert!(loaded_game.squares == game.squares);
+        assert_eq!(loaded_game.turn, game.turn);
+        assert_eq!(loaded_game.game_state, game.game_state);
+        assert_eq!(loaded_game.en_passant_susceptible_pawn, game.en_passant_susceptible_pawn);
+        assert_eq!(loaded_game.halfmove_clock, game.halfmove_clock);
+
+        // scenario 4: en passant target is the square behind the pawn that just moved
+        let mut game = Game::new();
+        game.make_move(Position::from_string("e2").unwrap(), Position::from_string("e4").unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1");
+
+        // scenario 5: a live en passant capture survives the round trip
+        game.load_fen("4k3/3p4/8/4P3/8/8/8/4K3 b - - 0 1").unwrap();
+        game.make_move(Position::from_string("d7").unwrap(), Position::from_string("d5").unwrap()).unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2");
+
+        let mut loaded_game = Game::from_fen(&game.to_fen()).unwrap();
+        assert!(loaded_game.is_capture(Position::from_string("e5").unwrap(), Position::from_string("d6").unwrap()));
+        loaded_game.make_move(Position::from_string("e5").unwrap(), Position::from_string("d6").unwrap()).unwrap();
+        assert_eq!(loaded_game.get_square(Position::from_string("d5").unwrap()), None);
+    }
+
+    #[test]
+    fn accessor_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position
+        assert_eq!(game.turn(), Color::White);
+        assert_eq!(game.game_state(), GameState::Normal);
+        assert_eq!(game.halfmove_clock(), 0);
+        assert_eq!(game.fullmove_number(), 1);
+        assert_eq!(game.en_passant_susceptible_pawn(), None);
+        assert_eq!(game.last_move(), None);
+        assert_eq!(game.checks_given(Color::White), 0);
+        assert_eq!(
+            game.castling_rights(),
+            CastlingRights { white_kingside: true, white_queenside: true, black_kingside: true, black_queenside: true }
+        );
+        assert!(game.squares() == &Game::new().squares);
+
+        // scenario 2: the accessors follow the moves made
+        let e2 = Position::from_string("e2").unwrap();
+        let e4 = Position::from_string("e4").unwrap();
+        game.make_move(e2, e4).unwrap();
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(game.en_passant_susceptible_pawn(), Some(e4));
+        assert_eq!(game.last_move(), Some((e2, e4)));
+
+        // scenario 3: castling rights and check counts after loading a position
+        game.load_fen("4k3/8/8/8/8/8/8/R3K3 b Q - 3 20").unwrap();
+        game.make_move(Position::from_string("e8").unwrap(), Position::from_string("d8").unwrap()).unwrap();
+        game.make_move(Position::from_string("a1").unwrap(), Position::from_string("a8").unwrap()).unwrap();
+        assert_eq!(game.game_state(), GameState::Check(Color::Black));
+        assert_eq!(game.checks_given(Color::White), 1);
+        assert_eq!(game.checks_given(Color::Black), 0);
+        assert_eq!(game.halfmove_clock(), 5);
+        assert_eq!(game.fullmove_number(), 21);
+        assert_eq!(
+            game.castling_rights(),
+            CastlingRights { white_kingside: false, white_queenside: false, black_kingside: false, black_queenside: false }
+        );
+    }
+
+    #[test]
+    fn can_castle_tests() {
+        let mut game = Game::new();
+        assert!(game.can_castle(Color::White, CastlingSide::Kingside));
+        assert!(game.can_castle(Color::Black, CastlingSide::Queenside));
+
+        // scenario 1: moving a rook loses the right on its side only
+        game.load_fen("r3k2r/8/8/8/8/8/8/R3K2R w KQkq - 0 1").unwrap();
+        game.make_move(Position::from_string("h1").unwrap(), Position::from_string("h2").unwrap()).unwrap();
+        assert!(!game.can_castle(Color::White, CastlingSide::Kingside));
+        assert!(game.can_castle(Color::White, CastlingSide::Queenside));
+
+        // scenario 2: moving the king loses both
+        game.make_move(Position::from_string("e8").unwrap(), Position::from_string("e7").unwrap()).unwrap();
+        assert!(!game.can_castle(Color::Black, CastlingSide::Kingside));
+        assert!(!game.can_castle(Color::Black, CastlingSide::Queenside));
+        assert!(game.to_fen().contains(" Q - "));
+
+        // scenario 3: the right stays even when castling is blocked
+        game.load_fen("r3k2r/8/8/8/8/8/8/R3KB1R w KQkq - 0 1").unwrap();
+        assert!(game.can_castle(Color::White, CastlingSide::Kingside));
+        assert!(game.castling_rights().can_castle(Color::White, CastlingSide::Kingside));
+    }
+
+    #[test]
+    fn lenient_fen_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: four fields are rejected by the strict loader but accepted by the lenient one
+        let fen = "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3";
+        assert_eq!(game.load_fen(fen), Err(FenError::WrongSegmentCount));
+        game.load_fen_lenient(fen).unwrap();
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(game.en_passant_susceptible_pawn(), Some(Position::from_string("e4").unwrap()));
+        assert_eq!(game.halfmove_clock(), 0);
+        assert_eq!(game.fullmove_number(), 1);
+        assert_eq!(game.to_fen(), format!("{} 0 1", fen));
+
+        // scenario 2: five fields keep the halfmove clock
+        game.load_fen_lenient("4k3/8/8/8/8/8/8/4K3 w - - 17").unwrap();
+        assert_eq!(game.halfmove_clock(), 17);
+        assert_eq!(game.fullmove_number(), 1);
+
+        // scenario 3: full fens load the same as with load_fen
+        game.load_fen_lenient("4k3/8/8/8/8/8/8/4K3 w - - 3 40").unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 3 40");
+
+        // scenario 4: fewer than four fields are still rejected
+        assert_eq!(game.load_fen_lenient("4k3/8/8/8/8/8/8/4K3 w -"), Err(FenError::WrongSegmentCount));
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/4K3 w - - 3 40");
+    }
+
+    #[test]
+    fn game_hash_tests() {
+        let mut positions = HashSet::new();
+
+        // scenario 1: the same position reached through different move orders
+        let mut game = Game::new();
+        game.make_move_san("Nf3").unwrap();
+        game.make_move_san("Nf6").unwrap();
+        game.make_move_san("Nc3").unwrap();
+        positions.insert(game);
+
+        let mut game = Game::new();
+        game.make_move_san("Nc3").unwrap();
+        game.make_move_san("Nf6").unwrap();
+        game.make_move_san("Nf3").unwrap();
+        positions.insert(game);
+        assert_eq!(positions.len(), 1);
+
+        // scenario 2: the clocks don't matter
+        positions.insert(Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap());
+        positions.insert(Game::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 30 70").unwrap());
+        assert_eq!(positions.len(), 2);
+
+        // scenario 3: the side to move does
+        positions.insert(Game::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap());
+        assert_eq!(positions.len(), 3);
+    }
+
+    #[test]
+    fn legal_moves_count_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.legal_moves_count(), 20);
+
+        // scenario 1: agrees with legal_moves
+        game.load_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.legal_moves_count(), 48);
+        assert_eq!(game.legal_moves_count(), game.legal_moves().len());
+
+        // scenario 2: nothing once the game is over or while waiting for a promotion
+        game.load_fen("k7/8/1Q6/8/8/8/8/K7 b - - 0 1").unwrap();
+        assert_eq!(game.legal_moves_count(), 0);
+        game.load_fen("4k3/1P5p/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move(Position::from_string("b7").unwrap(), Position::from_string("b8").unwrap()).unwrap();
+        assert_eq!(game.legal_moves_count(), 0);
+        assert!(game.legal_moves().is_empty());
+
+        // scenario 3: after a resignation every legal move method agrees there is nothing left to play
+        let mut game = Game::new();
+        game.resign(Color::White);
+        assert_eq!(game.legal_moves_count(), 0);
+        assert!(game.legal_moves().is_empty());
+        assert!(game.legal_moves_by_source().is_empty());
+        assert!(game.all_legal_moves_for(Color::Black).is_empty());
+        assert!(game.get_possible_moves(Position::from_string("e2").unwrap()).is_empty());
+        assert!(game.legal_destinations(Position::from_string("g1").unwrap()).is_empty());
+    }
+
+    #[test]
+    fn legal_moves_by_source_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position, only the pawns and knights can move
+        let moves = game.legal_moves_by_source();
+        assert_eq!(moves.len(), 10);
+        assert_eq!(moves.iter().map(|(_, destinations)| destinations.len()).sum::<usize>(), 20);
+        for (from, destinations) in &moves {
+            assert!(no_order_iters_eq(destinations.iter().copied(), game.get_possible_moves(*from).into_iter()));
+        }
+
+        // scenario 2: only the king can move out of a double check
+        game.load_fen("4k3/8/8/8/8/8/4B3/r3K2r w - - 0 1").unwrap();
+        let moves = game.legal_moves_by_source();
+        assert_eq!(moves.len(), 1);
+        assert_eq!(moves[0].0, Position::from_string("e1").unwrap());
+        assert!(no_order_iters_eq(
+            moves[0].1.iter().copied(),
+            [Position::from_string("d2").unwrap(), Position::from_string("f2").unwrap()].into_iter(),
+        ));
+
+        // scenario 3: checkmate leaves nothing
+        game.load_fen("4k3/8/8/8/8/8/r7/r3K3 w - - 0 1").unwrap();
+        assert!(game.legal_moves_by_source().is_empty());
+    }
+
+    #[test]
+    fn pinned_pieces_tests() {
+        let mut game = Game::new();
+        assert!(game.pinned_pieces(Color::White).is_empty());
+
+        // scenario 1: Bb5 pins the knight on c6 to the king on e8
+        game.load_fen("r1bqkbnr/pppp1ppp/2n5/1B2p3/4P3/5N2/PPPP1PPP/RNBQK2R b KQkq - 3 3").unwrap();
+        game.make_move_san("d6").unwrap();
+        assert_eq!(
+            game.pinned_pieces(Color::Black),
+            vec![(Position::from_string("c6").unwrap(), Position::from_string("b5").unwrap())]
+        );
+        assert!(game.pinned_pieces(Color::White).is_empty());
+
+        // scenario 2: rook and queen pins along files and ranks
+        game.load_fen("4k3/5r2/8/8/5N2/8/1q2BK2/8 w - - 0 1").unwrap();
+        assert_eq!(
+            game.pinned_pieces(Color::White),
+            vec![
+                (Position::from_string("f4").unwrap(), Position::from_string("f7").unwrap()),
+                (Position::from_string("e2").unwrap(), Position::from_string("b2").unwrap()),
+            ]
+        );
+
+        // scenario 3: two pieces in the way means neither is pinned
+        game.load_fen("4k3/8/8/8/8/8/1q1BBK2/8 w - - 0 1").unwrap();
+        assert!(game.pinned_pieces(Color::White).is_empty());
+
+        // scenario 4: a slider that can't move along the line isn't pinning
+        game.load_fen("4k3/8/8/8/8/8/1b2NK2/8 w - - 0 1").unwrap();
+        assert!(game.pinned_pieces(Color::White).is_empty());
+    }
+
+    #[test]
+    fn apply_moves_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: scholar's mate
+        let scholars_mate = [
+            (pos("e2"), pos("e4")),
+            (pos("e7"), pos("e5")),
+            (pos("f1"), pos("c4")),
+            (pos("b8"), pos("c6")),
+            (pos("d1"), pos("h5")),
+            (pos("g8"), pos("f6")),
+            (pos("h5"), pos("f7")),
+        ];
+        assert_eq!(game.apply_moves(&scholars_mate), Ok(()));
+        assert_eq!(game.game_state(), GameState::Checkmate(Color::Black));
+
+        // scenario 2: the moves before an illegal one stay made
+        let mut game = Game::new();
+        let moves = [(pos("e2"), pos("e4")), (pos("e7"), pos("e5")), (pos("e4"), pos("e5")), (pos("d2"), pos("d4"))];
+        assert_eq!(game.apply_moves(&moves), Err((2, MoveError::IllegalPattern)));
+        assert_eq!(game.move_history().len(), 2);
+        assert_eq!(game.turn(), Color::White);
+
+        // scenario 3: replaying another game's moves, including an underpromotion
+        let mut original = Game::from_fen("4k3/1P6/8/8/8/8/7P/4K3 w - - 0 1").unwrap();
+        original.make_move_promote(pos("b7"), pos("b8"), PieceType::Knight);
+        original.make_move(pos("e8"), pos("e7")).unwrap();
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/7P/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.replay_moves(original.move_history()), Ok(()));
+        assert_eq!(game.to_fen(), original.to_fen());
+
+        // scenario 4: promoting to a king is refused before the move is made
+        let mut game = Game::from_fen("4k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let mut promotion = original.move_history()[0];
+        promotion.promotion = Some(PieceType::King);
+        assert_eq!(game.replay_moves(&[promotion]), Err((0, MoveError::IllegalPattern)));
+        assert!(game.move_history().is_empty());
+    }
+
+    #[test]
+    fn insufficient_material_tests() {
+        let mut game = Game::new();
+        assert!(!game.is_insufficient_material());
+
+        let cases = [
+            // scenario 1: dead positions
+            ("4k3/8/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4KN2 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/2b1K3 w - - 0 1", true),
+            ("2b1k3/8/8/8/8/8/8/3BK3 w - - 0 1", true),
+            ("2b1k3/8/8/8/8/8/8/3BKB2 w - - 0 1", true),
+            // scenario 2: mate is still possible
+            ("4k3/8/8/8/8/8/8/4K2P w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/4K2R w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/4KNN1 w - - 0 1", false),
+            ("4kn2/8/8/8/8/8/8/4KB2 w - - 0 1", false),
+            ("3bk3/8/8/8/8/8/8/3BK3 w - - 0 1", false),
+        ];
+        for (fen, insufficient) in cases {
+            game.load_fen(fen).unwrap();
+            assert_eq!(game.is_insufficient_material(), insufficient, "{}", fen);
+        }
+
+        // scenario 3: it ends the game, except in variants that can be won without mating
+        game.load_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").unwrap();
+        assert_eq!(game.game_state(), GameState::InsufficientMaterial);
+        assert_eq!(game.get_game_result(), GameResult::Draw);
+
+        let mut game = GameBuilder::new().king_of_the_hill(true).starting_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").build().unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+        game = GameBuilder::new().three_check(true).starting_fen("4k3/8/8/8/8/8/8/4KN2 w - - 0 1").build().unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+    }
+
+    #[test]
+    fn ply_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.ply(), 0);
+
+        // scenario 1: every move counts, disallowed ones don't
+        game.make_move_san("e4").unwrap();
+        game.make_move_san("e5").unwrap();
+        game.make_move(Position::from_string("e4").unwrap(), Position::from_string("e5").unwrap()).unwrap_err();
+        game.make_move_san("Nf3").unwrap();
+        assert_eq!(game.ply(), 3);
+        assert_eq!(game.fullmove_number(), 2);
+
+        // scenario 2: loading a fen starts over, even late in a game
+        game.load_fen("4k3/7p/8/8/8/8/8/4K3 b - - 0 40").unwrap();
+        assert_eq!(game.ply(), 0);
+        game.make_move_san("Kd7").unwrap();
+        assert_eq!(game.ply(), 1);
+        assert_eq!(game.fullmove_number(), 41);
+
+        // scenario 3: so does clearing the board
+        game.clear_board();
+        assert_eq!(game.ply(), 0);
+    }
+
+    #[test]
+    fn promotion_only_for_moved_pawn_tests() {
+        let mut game = Game::from_fen("4k3/7p/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+
+        // scenario 1: a pawn put on the last rank by hand isn't promoted by the next move
+        game.set_square(Position::from_string("a8").unwrap(), Some(Piece { piece_type: PieceType::Pawn, color: Color::White }));
+        game.make_move_san("Kd2").unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+        game.make_move_san("h6").unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+
+        // scenario 2: the pawn that moves to the last rank is, even with another one already there
+        game.load_fen("4k3/1P5p/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.set_square(Position::from_string("a8").unwrap(), Some(Piece { piece_type: PieceType::Pawn, color: Color::White }));
+        game.make_move(Position::from_string("b7").unwrap(), Position::from_string("b8").unwrap()).unwrap();
+        assert_eq!(game.game_state(), GameState::AwaitingPromotion(Position::from_string("b8").unwrap()));
+        assert_eq!(game.promote(PieceType::Rook), MoveResult::Allowed);
+        assert_eq!(game.get_square(Position::from_string("b8").unwrap()), Some(Piece { piece_type: PieceType::Rook, color: Color::White }));
+    }
+
+    #[test]
+    fn fen_promotion_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: a white pawn already on the last rank
+        game.load_fen("1P2k3/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(game.game_state(), GameState::AwaitingPromotion(Position::from_string("b8").unwrap()));
+        assert_eq!(game.make_move(Position::from_string("e8").unwrap(), Position::from_string("d7").unwrap()), Err(MoveError::AwaitingPromotion));
+
+        // scenario 2: promoting carries on as after a move, here giving check
+        assert_eq!(game.promote(PieceType::Queen), MoveResult::Allowed);
+        assert_eq!(game.game_state(), GameState::Check(Color::Black));
+        assert_eq!(game.turn(), Color::Black);
+
+        // scenario 3: a black pawn on the first rank
+        game.load_fen("4k3/8/8/8/8/8/8/4K1p1 w - - 0 1").unwrap();
+        assert_eq!(game.game_state(), GameState::AwaitingPromotion(Position::from_string("g1").unwrap()));
+    }
+
+    #[test]
+    fn square_str_tests() {
+        let mut game = Game::new();
+        let white_queen = Piece { piece_type: PieceType::Queen, color: Color::White };
+
+        // scenario 1: occupied, empty and unparseable squares
+        assert_eq!(game.get_square_str("d1"), Some(Some(white_queen)));
+        assert_eq!(game.get_square_str("E4"), Some(None));
+        assert_eq!(game.get_square_str("i4"), None);
+        assert_eq!(game.get_square_str("e"), None);
+
+        // scenario 2: setting squares
+        assert_eq!(game.set_square_str("e4", Some(white_queen)), Ok(()));
+        assert_eq!(game.get_square(Position::new(4, 3)), Some(white_queen));
+        assert_eq!(game.set_square_str("d1", None), Ok(()));
+        assert!(game.is_empty_square(Position::new(3, 0)));
+        assert_eq!(game.set_square_str("e9", None), Err(PositionParseError::BadRank('9')));
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+    }
+
+    #[test]
+    #[cfg(feature = "wasm")]
+    fn wasm_tests() {
+        use crate::wasm::new_game;
+
+        let mut wasm_game = new_game();
+        let mut game = Game::new();
+
+        // scenario 1: the same moves, fen and state as the core api
+        assert_eq!(wasm_game.to_fen(), game.to_fen());
+        assert_eq!(wasm_game.get_possible_moves("g1"), vec!["f3", "h3"]);
+        assert!(no_order_iters_eq(
+            wasm_game.get_possible_moves("e2").iter().map(|to| to.parse().unwrap()),
+            game.get_possible_moves(Position::from_string("e2").unwrap()).into_iter()
+        ));
+        for (from, to) in [("f2", "f3"), ("e7", "e5"), ("g2", "g4"), ("d8", "h4")] {
+            assert!(wasm_game.make_move(from, to));
+            assert!(game.make_move(from.parse().unwrap(), to.parse().unwrap()).is_ok());
+        }
+        assert_eq!(wasm_game.to_fen(), game.to_fen());
+        assert_eq!(wasm_game.game_state_string(), "Checkmate(White)");
+
+        // scenario 2: bad input is turned down instead of panicking
+        let mut wasm_game = new_game();
+        assert!(!wasm_game.make_move("e2", "e5"));
+        assert!(!wasm_game.make_move("e2", "e9"));
+        assert!(wasm_game.get_possible_moves("z1").is_empty());
+        assert_eq!(wasm_game.game_state_string(), "Normal");
+
+        // scenario 3: positions in the state are written in algebraic notation
+        let mut wasm_game = new_game();
+        for (from, to) in [("a2", "a4"), ("b7", "b5"), ("a4", "b5"), ("g8", "f6"), ("b5", "b6"), ("f6", "g8"), ("b6", "a7"), ("g8", "f6"), ("a7", "b8")] {
+            assert!(wasm_game.make_move(from, to));
+        }
+        assert_eq!(wasm_game.game_state_string(), "AwaitingPromotion(b8)");
+
+        // scenario 4: finishing the promotion, kings, pawns and unknown pieces are turned down
+        for piece in ["k", "p", "x", "qq", ""] {
+            assert!(!wasm_game.promote(piece));
+        }
+        assert!(wasm_game.promote("n"));
+        assert_eq!(wasm_game.to_fen(), "rNbqkb1r/2pppppp/5n2/8/8/8/1PPPPPPP/RNBQKBNR b KQkq - 0 5");
+        assert_eq!(wasm_game.game_state_string(), "Normal");
+        assert!(!wasm_game.promote("q"));
+    }
+
+    #[test]
+    fn move_legality_tests() {
+        // scenario 1: a bishop pinned to its king can move along the pin, but not off it, and never like a rook
+        let game = Game::from_fen("4k3/7p/8/8/b7/8/2B5/3K4 w - - 0 1").unwrap();
+        assert_eq!(game.move_legality(pos("c2"), pos("b3")), MoveLegality::Legal);
+        assert_eq!(game.move_legality(pos("c2"), pos("a4")), MoveLegality::Legal);
+        assert_eq!(game.move_legality(pos("c2"), pos("d3")), MoveLegality::LeavesKingInCheck);
+        assert_eq!(game.move_legality(pos("c2"), pos("c5")), MoveLegality::Illegal);
+        assert_eq!(game.move_legality(pos("c2"), pos("c2")), MoveLegality::Illegal);
+        assert_eq!(game.move_legality(pos("c2"), pos("d1")), MoveLegality::Illegal);
+        assert_eq!(game.move_legality(pos("e4"), pos("e5")), MoveLegality::Illegal);
+
+        // scenario 2: it agrees with make_move
+        let mut moved = game.clone();
+        assert_eq!(moved.make_move(pos("c2"), pos("d3")), Err(MoveError::WouldBeInCheck));
+        assert_eq!(moved.make_move(pos("c2"), pos("c5")), Err(MoveError::IllegalPattern));
+
+        // scenario 3: castling through check, and whose turn it is doesn't matter
+        let game = Game::from_fen("4k3/8/8/8/8/8/5r2/4K2R b K - 0 1").unwrap();
+        assert_eq!(game.move_legality(pos("e1"), pos("g1")), MoveLegality::LeavesKingInCheck);
+        assert_eq!(game.move_legality(pos("h1"), pos("h2")), MoveLegality::Legal);
+    }
+
+    #[test]
+    fn squares_in_rank_order_tests() {
+        let game = Game::from_fen("r3k3/8/8/8/8/8/8/4K2R w - - 0 1").unwrap();
+        let squares: Vec<(Position, Square)> = game.squares_in_rank_order().collect();
+
+        // scenario 1: a8 first, then along the rank, h1 last
+        assert_eq!(squares.len(), 64);
+        assert_eq!(squares[0], (Position::from_string("a8").unwrap(), Some(Piece { piece_type: PieceType::Rook, color: Color::Black })));
+        assert_eq!(squares[1].0, Position::from_string("b8").unwrap());
+        assert_eq!(squares[8].0, Position::from_string("a7").unwrap());
+        assert_eq!(squares[63], (Position::from_string("h1").unwrap(), Some(Piece { piece_type: PieceType::Rook, color: Color::White })));
+
+        // scenario 2: every square matches get_square
+        assert!(squares.iter().all(|(pos, square)| game.get_square(*pos) == *square));
+    }
+
+    #[test]
+    fn checkmate_in_one_tests() {
+        // scenario 1: back rank mate, the only mating move
+        let game = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        assert_eq!(game.checkmate_in_one(), vec![(pos("a1"), pos("a8"))]);
+
+        // scenario 2: no mate from the starting position, or once the game is over
+        assert!(Game::new().checkmate_in_one().is_empty());
+        let mut game = Game::from_fen("6k1/5ppp/8/8/8/8/5PPP/R5K1 w - - 0 1").unwrap();
+        game.make_move(pos("a1"), pos("a8")).unwrap();
+        assert!(game.checkmate_in_one().is_empty());
+
+        // scenario 3: mate by promoting, and black can mate too
+        let game = Game::from_fen("6k1/P4ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(game.checkmate_in_one(), vec![(pos("a7"), pos("a8"))]);
+        let game = Game::from_fen("r5k1/8/8/8/8/8/5PPP/6K1 b - - 0 1").unwrap();
+        assert_eq!(game.checkmate_in_one(), vec![(pos("a8"), pos("a1"))]);
+    }
+
+    #[test]
+    fn legal_destinations_tests() {
+        let hint = |to: &str| MoveHint { to: pos(to), is_castle: false, is_en_passant: false, promotes: false };
+
+        // scenario 1: a pawn about to promote, pushing or capturing
+        let game = Game::from_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let hints = game.legal_destinations(pos("a7"));
+        assert_eq!(hints.len(), 2);
+        assert!(hints.contains(&MoveHint { promotes: true, ..hint("a8") }));
+        assert!(hints.contains(&MoveHint { promotes: true, ..hint("b8") }));
+
+        // scenario 2: castling, next to ordinary king moves
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let hints = game.legal_destinations(pos("e1"));
+        assert!(hints.contains(&MoveHint { is_castle: true, ..hint("g1") }));
+        assert!(hints.contains(&hint("f1")));
+        assert_eq!(hints.iter().filter(|hint| hint.is_castle).count(), 1);
+
+        // scenario 3: en passant, the same destinations as get_possible_moves
+        let game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let hints = game.legal_destinations(pos("e5"));
+        assert_eq!(hints.len(), 2);
+        assert!(hints.contains(&hint("e6")));
+        assert!(hints.contains(&MoveHint { is_en_passant: true, ..hint("d6") }));
+        assert!(no_order_iters_eq(hints.iter().map(|hint| hint.to), game.get_possible_moves(pos("e5")).into_iter()));
+        assert!(game.legal_destinations(pos("e4")).is_empty());
+
+        // scenario 4: in atomic, a pawn capturing onto the last rank is blown up instead of promoting
+        let game = GameBuilder::new().atomic(true).starting_fen("1r2k3/P7/8/8/8/8/8/4K3 w - - 0 1").build().unwrap();
+        assert!(game.legal_destinations(pos("a7")).contains(&hint("b8")));
+    }
+
+    #[test]
+    fn opponent_in_check_state_tests() {
+        // scenario 1: black's king is mated, but it's white's move, so nothing is reported
+        let game = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+        assert_ne!(game.get_game_result(), GameResult::BlackWins);
+        assert_eq!(game.validate_position(), Err(PositionLegalityError::OpponentInCheck));
+
+        // scenario 2: the side to move is still the one checkmated when both kings are attacked
+        let game = Game::from_fen("R5k1/5ppp/8/8/8/8/P4PPP/r5K1 w - - 0 1").unwrap();
+        assert_eq!(game.game_state(), GameState::Checkmate(Color::White));
+        assert_eq!(game.get_game_result(), GameResult::BlackWins);
+    }
+
+    #[test]
+    fn array_tests() {
+        // scenario 1: the layout is rank 8 first, from the a-file
+        let game = Game::new();
+        let board = game.to_array();
+        assert_eq!(board[0][0], Some(Piece { piece_type: PieceType::Rook, color: Color::Black }));
+        assert_eq!(board[7][4], Some(Piece { piece_type: PieceType::King, color: Color::White }));
+        assert_eq!(board[6], [Some(Piece { piece_type: PieceType::Pawn, color: Color::White }); 8]);
+        assert_eq!(board[3], [None; 8]);
+
+        // scenario 2: round trip, without castling rights
+        let round_trip = Game::from_array(board, Color::White);
+        assert_eq!(round_trip.to_array(), board);
+        assert_eq!(round_trip.to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1");
+        let mut game = Game::from_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w - - 4 4").unwrap();
+        game.make_move_san("Qxf7").unwrap();
+        let round_trip = Game::from_array(game.to_array(), Color::Black);
+        assert!(round_trip == game);
+
+        // scenario 3: the game state is worked out
+        assert_eq!(round_trip.game_state(), GameState::Checkmate(Color::Black));
+        let mut board = [[None; 8]; 8];
+        board[0][0] = Some(Piece { piece_type: PieceType::King, color: Color::Black });
+        board[7][7] = Some(Piece { piece_type: PieceType::King, color: Color::White });
+        assert_eq!(Game::from_array(board, Color::Black).game_state(), GameState::InsufficientMaterial);
+    }
+
+    #[test]
+    fn try_get_square_tests() {
+        let game = Game::new();
+
+        // scenario 1: positions on the board work like get_square
+        assert_eq!(game.try_get_square(Position::new(4, 0)), Some(Some(Piece { piece_type: PieceType::King, color: Color::White })));
+        assert_eq!(game.try_get_square(Position::new(4, 4)), Some(None));
+        assert!(Position::new(7, 7).is_on_board());
+
+        // scenario 2: positions built by hand off the board, (8, 1) would otherwise be read as a1
+        let off_board = Position { x: 8, y: 1 };
+        assert!(!off_board.is_on_board());
+        assert_eq!(game.try_get_square(off_board), None);
+        assert_eq!(game.try_get_square(Position { x: 0, y: 8 }), None);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn off_board_get_square_test() {
+        let _ = Game::new().get_square(Position { x: 8, y: 1 });
+    }
+
+    #[test]
+    fn attacks_from_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: pawns attack diagonally but move forward
+        assert!(no_order_iters_eq(game.attacks_from(pos("e2")).into_iter(), [pos("d3"), pos("f3")].into_iter()));
+        assert!(no_order_iters_eq(game.get_possible_moves(pos("e2")).into_iter(), [pos("e3"), pos("e4")].into_iter()));
+        assert!(no_order_iters_eq(game.attacks_from(pos("a7")).into_iter(), [pos("b6")].into_iter()));
+        assert!(game.attacks_from(pos("e4")).is_empty());
+
+        // scenario 2: sliders stop at the first piece of either color, and pins don't matter
+        game.load_fen("4k3/4r3/8/8/1p2R2P/8/8/4K3 w - - 0 1").unwrap();
+        assert!(no_order_iters_eq(
+            game.attacks_from(pos("e4")).into_iter(),
+            ["e5", "e6", "e7", "d4", "c4", "b4", "f4", "g4", "h4", "e3", "e2", "e1"].iter().map(|square| pos(square)),
+        ));
+
+        // scenario 3: a blocked pawn still attacks, and a knight attacks its own pieces' squares too
+        game.load_fen("4k3/8/8/8/4p3/4P3/8/4KN2 w - - 0 1").unwrap();
+        assert!(game.get_possible_moves(pos("e3")).is_empty());
+        assert!(no_order_iters_eq(game.attacks_from(pos("e3")).into_iter(), [pos("d4"), pos("f4")].into_iter()));
+        assert!(no_order_iters_eq(game.attacks_from(pos("f1")).into_iter(), [pos("d2"), pos("e3"), pos("g3"), pos("h2")].into_iter()));
+
+        // scenario 4: agrees with get_attackers
+        game.load_fen("r1bqkb1r/pppp1ppp/2n2n2/4p2Q/2B1P3/8/PPPP1PPP/RNB1K1NR w KQkq - 4 4").unwrap();
+        for x in 0..=7 {
+            for y in 0..=7 {
+                let from = Position::new(x, y);
+                let Some(piece) = game.get_square(from) else { continue };
+                for to in game.attacks_from(from) {
+                    assert!(game.get_attackers(to, piece.color).contains(&from));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn en_passant_target_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.en_passant_target(), None);
+
+        // scenario 1: after a double pawn move
+        game.make_move_san("e4").unwrap();
+        assert_eq!(game.en_passant_target(), Some(Position::from_string("e3").unwrap()));
+        game.make_move_san("Nf6").unwrap();
+        assert_eq!(game.en_passant_target(), None);
+
+        // scenario 2: matches the fen's en passant field after loading
+        for (fen, target) in [
+            ("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq d6 0 2", Some("d6")),
+            ("rnbqkbnr/pppppppp/8/8/P7/8/1PPPPPPP/RNBQKBNR b KQkq a3 0 1", Some("a3")),
+            ("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1", None),
+        ] {
+            game.load_fen(fen).unwrap();
+            assert_eq!(game.en_passant_target(), target.map(|square| Position::from_string(square).unwrap()), "{}", fen);
+            assert_eq!(game.to_fen(), fen);
+        }
+    }
+
+    #[test]
+    fn make_move_trusted_tests() {
+        // scenario 1: replaying a game with en passant, promotion and castling
+        let mut original = Game::new();
+        for san in ["e4", "d5", "exd5", "c5", "dxc6", "Nf6", "cxb7", "Bd7", "bxa8=Q", "e6", "Nf3", "Bd6", "Be2", "O-O", "O-O"] {
+            original.make_move_san(san).unwrap();
+        }
+
+        let mut game = Game::new();
+        for trusted_move in original.move_history() {
+            game.make_move_trusted(trusted_move);
+        }
+        assert_eq!(game.to_fen(), original.to_fen());
+        assert_eq!(game.game_state(), original.game_state());
+        assert_eq!(game.move_history(), original.move_history());
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+
+        // scenario 2: the game state is still updated
+        let mut game = Game::new();
+        let mut original = Game::new();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            game.make_move_trusted(&original.make_move_san(san).unwrap());
+        }
+        assert_eq!(game.game_state(), GameState::Checkmate(Color::White));
+    }
+
+    #[test]
+    fn find_pieces_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: start position
+        let white_pawns = game.find_pieces(PieceType::Pawn, Color::White);
+        assert_eq!(white_pawns.len(), 8);
+        assert!(white_pawns.iter().all(|pos| pos.y == 1));
+        assert!(no_order_iters_eq(
+            game.find_pieces(PieceType::Rook, Color::Black).into_iter(),
+            [Position::from_string("a8").unwrap(), Position::from_string("h8").unwrap()].into_iter(),
+        ));
+        assert_eq!(game.find_pieces(PieceType::King, Color::White), vec![Position::from_string("e1").unwrap()]);
+
+        // scenario 2: nothing found
+        game.load_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert!(game.find_pieces(PieceType::Queen, Color::Black).is_empty());
+    }
+
+    #[test]
+    fn promotion_self_check_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: a pinned pawn can't promote by pushing, whatever it would become, and nothing is changed
+        for piece_type in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+            game.load_fen("k4b2/6P1/7K/8/8/8/8/8 w - - 0 1").unwrap();
+            assert_eq!(game.make_move_promote(pos("g7"), pos("g8"), piece_type), MoveResult::Disallowed);
+            assert_eq!(game.to_fen(), "k4b2/6P1/7K/8/8/8/8/8 w - - 0 1");
+            assert_eq!(game.game_state(), GameState::Normal);
+        }
+
+        // scenario 2: capturing the pinning piece is fine
+        assert_eq!(game.make_move_promote(pos("g7"), pos("f8"), PieceType::Knight), MoveResult::Allowed);
+        assert_eq!(game.get_square(pos("f8")), Some(Piece { piece_type: PieceType::Knight, color: Color::White }));
+
+        // scenario 3: promoting to block a check works with every piece
+        for piece_type in [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight] {
+            game.load_fen("K6r/4P3/8/8/8/8/8/6k1 w - - 0 1").unwrap();
+            assert_eq!(game.game_state(), GameState::Check(Color::White));
+            assert_eq!(game.make_move_promote(pos("e7"), pos("e8"), piece_type), MoveResult::Allowed);
+            assert!(!game.is_in_check(Color::White));
+        }
+    }
+
+    #[test]
+    fn bytes_tests() {
+        // scenario 1: random games, checking the round trip after every move
+        play_random_games(20, 80, |game| {
+            let bytes = game.to_bytes();
+            assert_eq!(bytes.len(), 42);
+            let decoded = Game::from_bytes(&bytes).unwrap();
+            assert!(decoded == *game);
+            assert_eq!(decoded.to_fen(), game.to_fen());
+            assert_eq!(decoded.to_bytes(), bytes);
+        });
+
+        // scenario 2: the game state is worked out again after decoding
+        let game = Game::from_fen("k7/8/1Q6/8/8/8/8/K7 b - - 12 60").unwrap();
+        let decoded = Game::from_bytes(&game.to_bytes()).unwrap();
+        assert_eq!(decoded.game_state(), GameState::Stalemate(Color::Black));
+        assert_eq!(decoded.halfmove_clock(), 12);
+        assert_eq!(decoded.fullmove_number(), 60);
+
+        // scenario 3: malformed bytes
+        let bytes = Game::new().to_bytes();
+        assert_eq!(Game::from_bytes(&bytes[..41]).err(), Some(BytesError::WrongLength(41)));
+        assert_eq!(Game::from_bytes(&[bytes.clone(), vec![0]].concat()).err(), Some(BytesError::WrongLength(43)));
+
+        let mut bad_square = bytes.clone();
+        bad_square[20] = 0x07;
+        assert_eq!(Game::from_bytes(&bad_square).err(), Some(BytesError::BadSquare(7)));
+        bad_square[20] = 0x80;
+        assert_eq!(Game::from_bytes(&bad_square).err(), Some(BytesError::BadSquare(8)));
+
+        let mut bad_flags = bytes.clone();
+        bad_flags[32] |= 0x40;
+        assert!(matches!(Game::from_bytes(&bad_flags).err(), Some(BytesError::BadFlags(_))));
+
+        let mut bad_en_passant = bytes.clone();
+        bad_en_passant[33] = 64;
+        assert_eq!(Game::from_bytes(&bad_en_passant).err(), Some(BytesError::BadEnPassant(64)));
+        bad_en_passant[33] = 0;
+        assert_eq!(Game::from_bytes(&bad_en_passant).err(), Some(BytesError::BadEnPassant(0)));
+
+        // the pawn on e5 can be taken en passant with white to move, but not with black to move
+        let mut en_passant = bytes.clone();
+        en_passant[33] = Position::from_string("e5").unwrap().to_index() as u8;
+        assert!(Game::from_bytes(&en_passant).is_ok());
+        en_passant[32] |= 1;
+        assert_eq!(Game::from_bytes(&en_passant).err(), Some(BytesError::BadEnPassant(28)));
+
+        let mut bad_fullmove_number = bytes.clone();
+        bad_fullmove_number[38..42].copy_from_slice(&0u32.to_le_bytes());
+        assert_eq!(Game::from_bytes(&bad_fullmove_number).err(), Some(BytesError::BadFullmoveNumber(0)));
+    }
+
+    #[test]
+    fn fullmove_number_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.fullmove_number, 1);
+
+        game.make_move(Position::from_string("e2").unwrap(), Position::from_string("e4").unwrap()).unwrap();
+        assert_eq!(game.fullmove_number, 1);
+
+        game.make_move(Position::from_string("e7").unwrap(), Position::from_string("e5").unwrap()).unwrap();
+        assert_eq!(game.fullmove_number, 2);
+        assert!(game.to_fen().ends_with(" 2"));
+
+        game.load_fen("4k3/8/8/8/8/8/8/4K3 w - - 0 37").unwrap();
+        assert_eq!(game.fullmove_number, 37);
+    }
+
+    #[test]
+    fn halfmove_clock_tests() {
+        let mut game = Game::new();
+
+        let white_pawn_moves = ["a2a3", "a3a4", "b2b3", "b3b4", "c2c3", "c3c4", "d2d3", "d3d4", "e2e3", "e3e4"];
+        let black_pawn_moves = ["a7a6", "a6a5", "b7b6", "b6b5", "c7c6", "c6c5", "d7d6", "d6d5", "e7e6", "e6e5"];
+
+        // 60 halfmoves where a pawn moves every few halfmoves
+        for (white_move, black_move) in white_pawn_moves.iter().zip(black_pawn_moves.iter()) {
+            for mv in [*white_move, *black_move, "g1f3", "g8f6", "f3g1", "f6g8"] {
+                let res = game.make_move(Position::from_string(&mv[0..2]).unwrap(), Position::from_string(&mv[2..4]).unwrap());
+                assert!(res.is_ok());
+            }
+        }
+
+        assert_eq!(game.halfmove_clock, 4);
+        assert_eq!(game.game_state, GameState::Normal);
+    }
+
+    #[test]
+    fn clear_board_tests() {
+        let mut game = Game::new();
+        for san in ["e4", "d5", "exd5", "Qxd5", "Nc3"] {
+            game.make_move_san(san).unwrap();
+        }
+        game.clear_board();
+
+        assert!(game.squares.iter().all(|square| square.is_none()));
+        assert!(!game.white_castling_kingside_available);
+        assert!(!game.white_castling_queenside_available);
+        assert!(!game.black_castling_kingside_available);
+        assert!(!game.black_castling_queenside_available);
+        assert_eq!(game.to_fen(), "8/8/8/8/8/8/8/8 w - - 0 1");
+        assert!(game.move_history().is_empty());
+        assert_eq!(game.last_move, None);
+        assert_eq!(game.black_checks_given, 0);
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+    }
+
+    #[test]
+    fn validate_position_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.validate_position(), Ok(()));
+
+        let cases = [
+            ("8/8/8/8/8/8/8/4K3 w - - 0 1", PositionLegalityError::WrongKingCount(Color::Black)),
+            ("4k3/8/8/8/8/8/8/3KK3 w - - 0 1", PositionLegalityError::WrongKingCount(Color::White)),
+            ("4k3/8/8/8/8/NNNNNNNN/PPPPPPPP/4K3 w - - 0 1", PositionLegalityError::TooManyPieces(Color::White)),
+            ("4k3/pppppppp/p7/8/8/8/8/4K3 w - - 0 1", PositionLegalityError::TooManyPawns(Color::Black)),
+            ("4k2P/8/8/8/8/8/8/4K3 w - - 0 1", PositionLegalityError::PawnOnBackRank(Position::from_string("h8").unwrap())),
+            ("4k2R/8/8/8/8/8/8/4K3 w - - 0 1", PositionLegalityError::OpponentInCheck),
+        ];
+        for (fen, error) in cases {
+            game.load_fen(fen).unwrap();
+            assert_eq!(game.validate_position(), Err(error), "{}", fen);
+        }
+
+        // being in check is fine for the side to move
+        game.load_fen("4k2R/8/8/8/8/8/8/4K3 b - - 0 1").unwrap();
+        assert_eq!(game.validate_position(), Ok(()));
+    }
+
+    #[test]
+    fn set_piece_tests() {
+        let mut game = Game::new();
+        game.clear_board();
+        game.turn = Color::Black;
+
+        // scenario 1: setting up a position piece by piece
+        game.set_piece(Position::from_string("e1").unwrap(), PieceType::King, Color::White).unwrap();
+        game.set_piece(Position::from_string("h8").unwrap(), PieceType::King, Color::Black).unwrap();
+        game.set_piece(Position::from_string("g1").unwrap(), PieceType::Rook, Color::White).unwrap();
+        assert_eq!(game.to_fen().split(' ').next(), Some("7k/8/8/8/8/8/8/4K1R1"));
+        assert_eq!(game.game_state, GameState::Normal);
+
+        // scenario 2: the game state follows, a second rook on the h-file gives mate
+        game.set_piece(Position::from_string("h1").unwrap(), PieceType::Rook, Color::White).unwrap();
+        assert_eq!(game.game_state, GameState::Checkmate(Color::Black));
+        game.remove_piece(Position::from_string("h1").unwrap());
+        assert_eq!(game.get_square(Position::from_string("h1").unwrap()), None);
+        assert_eq!(game.game_state, GameState::Normal);
+
+        // scenario 3: replacing a piece
+        game.set_piece(Position::from_string("g1").unwrap(), PieceType::Queen, Color::Black).unwrap();
+        assert_eq!(game.get_square(Position::from_string("g1").unwrap()), Some(Piece { piece_type: PieceType::Queen, color: Color::Black }));
+        assert_eq!(game.game_state, GameState::Normal);
+        game.set_turn(Color::White);
+        assert_eq!(game.game_state, GameState::Check(Color::White));
+
+        // scenario 4: a second king is refused without changing anything
+        let fen = game.to_fen();
+        assert_eq!(game.set_piece(Position::from_string("a1").unwrap(), PieceType::King, Color::White), Err(SetupError::DuplicateKing(Color::White)));
+        assert_eq!(game.to_fen(), fen);
+        game.set_piece(Position::from_string("e1").unwrap(), PieceType::King, Color::White).unwrap();
+
+        // scenario 5: the history starts over from the edited position, so pgn export describes the current board
+        let mut game = Game::new();
+        game.make_move_san("e4").unwrap();
+        game.remove_piece(Position::from_string("d7").unwrap());
+        assert_eq!(game.ply(), 0);
+        assert_eq!(game.last_move, None);
+        assert_eq!(game.position_history, vec![game.zobrist_hash]);
+        game.make_move_san("Qd6").unwrap();
+        let fen = game.to_fen();
+        let mut loaded = Game::new();
+        loaded.load_pgn(&game.to_pgn()).unwrap();
+        assert_eq!(loaded.to_fen(), fen);
+    }
+
+    #[test]
+    fn piece_count_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.piece_count(), 32);
+        assert!(game.is_empty_square(Position::from_string("e4").unwrap()));
+        assert!(!game.is_empty_square(Position::from_string("e2").unwrap()));
+
+        game.make_move_san("e4").unwrap();
+        assert!(!game.is_empty_square(Position::from_string("e4").unwrap()));
+        assert!(game.is_empty_square(Position::from_string("e2").unwrap()));
+
+        game.load_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.piece_count(), 4);
+        game.make_move_san("exd5").unwrap();
+        assert_eq!(game.piece_count(), 3);
+    }
+
+    #[test]
+    fn material_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position
+        assert_eq!(game.pieces_of(Color::White).count(), 16);
+        assert!(game.pieces_of(Color::Black).all(|(_, piece)| piece.color == Color::Black));
+        assert_eq!(game.count_material(Color::White), 39);
+        assert_eq!(game.count_material(Color::Black), 39);
+        assert_eq!(game.material_balance(), 0);
+
+        // scenario 2: white is up a knight for a pawn
+        game.load_fen("4k3/4p3/8/8/8/8/3N4/4K3 w - - 0 1").unwrap();
+        assert_eq!(game.pieces_of(Color::White).count(), 2);
+        assert!(game.pieces_of(Color::White).any(|(pos, _)| pos == Position::from_string("d2").unwrap()));
+        assert_eq!(game.count_material(Color::White), 3);
+        assert_eq!(game.count_material(Color::Black), 1);
+        assert_eq!(game.material_balance(), 2);
+    }
+
+    #[test]
+    fn piece_type_value_tests() {
+        // scenario 1: values and names
+        let piece_types = [PieceType::Pawn, PieceType::Knight, PieceType::Bishop, PieceType::Rook, PieceType::Queen, PieceType::King];
+        assert_eq!(piece_types.map(|piece_type| piece_type.value()), [1, 3, 3, 5, 9, 0]);
+        assert_eq!(piece_types.map(|piece_type| piece_type.name()), ["Pawn", "Knight", "Bishop", "Rook", "Queen", "King"]);
+
+        // scenario 2: the constants work in const contexts
+        const MINOR_PIECES: u32 = PieceType::KNIGHT_VALUE + PieceType::BISHOP_VALUE;
+        const QUEEN: u32 = PieceType::Queen.value();
+        assert_eq!(MINOR_PIECES, 6);
+        assert_eq!(QUEEN, PieceType::QUEEN_VALUE);
+    }
+
+    #[test]
+    fn king_position_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.get_king_position(Color::White), Some(Position::from_string("e1").unwrap()));
+        assert_eq!(game.get_king_position(Color::Black), Some(Position::from_string("e8").unwrap()));
+
+        game.set_square(Position::from_string("e8").unwrap(), None);
+        assert_eq!(game.get_king_position(Color::Black), None);
+    }
+
+    #[test]
+    fn is_in_check_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: nobody in check
+        assert!(!game.is_in_check(Color::White));
+        assert!(!game.is_in_check(Color::Black));
+
+        // scenario 2: check
+        game.load_fen("4k3/8/8/8/8/8/8/R3K2r w - - 0 1").unwrap();
+        assert!(game.is_in_check(Color::White));
+        assert!(!game.is_in_check(Color::Black));
+
+        // scenario 3: checkmate
+        game.load_fen("7k/5N1p/8/8/8/8/8/2K3R1 b KQkq - 0 1").unwrap();
+        assert!(game.is_in_check(Color::Black));
+        assert!(!game.is_in_check(Color::White));
+
+        // scenario 4: every kind of attacker, and blocked sliders
+        let checks = [
+            ("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/4p3/4K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/3n4/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/3kK3 w - - 0 1", true),
+            ("4k3/8/8/b7/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/b7/8/2P5/8/4K3 w - - 0 1", false),
+            ("4k3/8/8/8/8/8/8/q2NK3 w - - 0 1", false),
+            ("4k3/4q3/8/8/8/8/8/4K3 w - - 0 1", true),
+            ("4k3/8/8/8/8/8/8/4K2b w - - 0 1", false),
+        ];
+        for (fen, in_check) in checks {
+            game.load_fen(fen).unwrap();
+            assert_eq!(game.is_in_check(Color::White), in_check, "{}", fen);
+        }
+    }
+
+    #[test]
+    fn is_square_attacked_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: pawns attack the empty squares diagonally in front of them, not the ones they can push to
+        assert!(game.is_square_attacked(Position::from_string("d3").unwrap(), Color::White));
+        assert!(game.is_square_attacked(Position::from_string("f6").unwrap(), Color::Black));
+        assert!(!game.is_square_attacked(Position::from_string("e4").unwrap(), Color::White));
+        assert!(!game.is_square_attacked(Position::from_string("e5").unwrap(), Color::Black));
+
+        // scenario 2: a pinned knight still attacks
+        game.load_fen("4k3/4r3/8/8/8/8/4N3/4K3 w - - 0 1").unwrap();
+        assert!(game.get_possible_moves(Position::from_string("e2").unwrap()).is_empty());
+        assert!(game.is_square_attacked(Position::from_string("d4").unwrap(), Color::White));
+        assert!(game.is_square_attacked(Position::from_string("g1").unwrap(), Color::White));
+
+        // scenario 3: sliders are blocked by pieces in between
+        assert!(game.is_square_attacked(Position::from_string("e3").unwrap(), Color::Black));
+        assert!(!game.is_square_attacked(Position::from_string("e1").unwrap(), Color::Black));
+    }
+
+    #[test]
+    fn get_attackers_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: two rooks and a bishop on one square, with a queen blocked by a pawn
+        game.load_fen("4k3/3r4/1B6/8/Qp5R/8/8/3R2K1 w - - 0 1").unwrap();
+        let attackers = game.get_attackers(Position::from_string("d4").unwrap(), Color::White);
+        assert_eq!(attackers.len(), 3);
+        assert!(no_order_iters_eq(
+            attackers.into_iter(),
+            ["b6", "d1", "h4"].iter().map(|pos| Position::from_string(pos).unwrap())
+        ));
+        assert_eq!(game.get_attackers(Position::from_string("d4").unwrap(), Color::Black), vec![Position::from_string("d7").unwrap()]);
+
+        // scenario 2: double check
+        game.load_fen("4k3/8/5N2/8/8/8/8/4RK2 b - - 0 1").unwrap();
+        assert_eq!(game.get_attackers(Position::from_string("e8").unwrap(), Color::White).len(), 2);
+
+        // scenario 3: nothing attacks
+        assert!(game.get_attackers(Position::from_string("a8").unwrap(), Color::White).is_empty());
+    }
+
+    #[test]
+    fn checking_pieces_tests() {
+        let mut game = Game::new();
+        assert!(game.checking_pieces().is_empty());
+
+        // scenario 1: single check
+        game.load_fen("4k3/8/8/8/8/8/8/R3K2r w - - 0 1").unwrap();
+        assert_eq!(game.checking_pieces(), vec![Position::from_string("h1").unwrap()]);
+
+        // scenario 2: discovered double check, only the king can move
+        game.load_fen("4k3/8/8/8/4N3/8/8/4R1K1 w - - 0 1").unwrap();
+        game.make_move_san("Nf6+").unwrap();
+        assert!(no_order_iters_eq(
+            game.checking_pieces().into_iter(),
+            ["e1", "f6"].iter().map(|pos| Position::from_string(pos).unwrap())
+        ));
+        assert_eq!(game.checking_pieces().len(), 2);
+        assert!(game
+            .legal_moves()
+            .iter()
+            .all(|(from, _)| *from == Position::from_string("e8").unwrap()));
+    }
+
+    #[test]
+    fn check_check_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: white's only way out of check also checks black, which isn't checkmate
+        game.load_fen("8/8/8/8/8/7N/4k1PP/r6K w - - 0 1").unwrap();
+        assert_eq!(game.game_state, GameState::Check(Color::White));
+        assert_eq!(
+            game.get_possible_moves(Position::from_string("h3").unwrap()),
+            vec![Position::from_string("g1").unwrap()]
+        );
+
+        // scenario 2: a missing king is never in check
+        game.load_fen("k7/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        game.set_square(Position::from_string("a8").unwrap(), None);
+        let check_status = check_check(&game);
+        assert!(!check_status.white);
+        assert!(!check_status.black);
+
+        // scenario 3: both kings attacked are reported independently
+        game.load_fen("r3k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        game.set_square(Position::from_string("a8").unwrap(), None);
+        game.set_square(Position::from_string("e5").unwrap(), Some(Piece { piece_type: PieceType::Rook, color: Color::White }));
+        game.set_square(Position::from_string("e4").unwrap(), Some(Piece { piece_type: PieceType::Rook, color: Color::Black }));
+        let check_status = check_check(&game);
+        assert!(check_status.white);
+        assert!(check_status.black);
+    }
+
+    #[test]
+    fn make_move_error_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: errors
+        assert_eq!(game.make_move(Position::from_string("e4").unwrap(), Position::from_string("e5").unwrap()), Err(MoveError::EmptySource));
+        assert_eq!(game.make_move(Position::from_string("e7").unwrap(), Position::from_string("e5").unwrap()), Err(MoveError::WrongTurn));
+        assert_eq!(game.make_move(Position::from_string("a1").unwrap(), Position::from_string("a2").unwrap()), Err(MoveError::FriendlyFire));
+        assert_eq!(game.make_move(Position::from_string("e2").unwrap(), Position::from_string("e5").unwrap()), Err(MoveError::IllegalPattern));
+
+        // scenario 2: pinned piece and castling through check
+        game.load_fen("4k3/4r3/8/8/8/8/4B3/4K2R w K - 0 1").unwrap();
+        assert_eq!(game.make_move(Position::from_string("e2").unwrap(), Position::from_string("d3").unwrap()), Err(MoveError::WouldBeInCheck));
+        game.load_fen("4k3/5r2/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert_eq!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap()), Err(MoveError::WouldBeInCheck));
+        assert_eq!(MoveError::WouldBeInCheck.to_string(), "the move would leave the king in check");
+
+        // scenario 3: capture
+        game.load_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        let made_move = game.make_move(Position::from_string("e4").unwrap(), Position::from_string("d5").unwrap()).unwrap();
+        assert_eq!(made_move.captured, Some(Piece { piece_type: PieceType::Pawn, color: Color::Black }));
+        assert!(!made_move.is_castle && !made_move.is_en_passant);
+
+        // scenario 4: en passant
+        game.load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let made_move = game.make_move(Position::from_string("e5").unwrap(), Position::from_string("d6").unwrap()).unwrap();
+        assert_eq!(made_move.captured, Some(Piece { piece_type: PieceType::Pawn, color: Color::Black }));
+        assert!(made_move.is_en_passant);
+
+        // scenario 5: castling
+        game.load_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        let made_move = game.make_move(Position::from_string("e1").unwrap(), Position::from_string("g1").unwrap()).unwrap();
+        assert!(made_move.is_castle);
+        assert_eq!(made_move.captured, None);
+
+        // scenario 6: game over
+        game.load_fen("7k/5N1p/8/8/8/8/8/2K3R1 b KQkq - 0 1").unwrap();
+        assert_eq!(game.make_move(Position::from_string("h8").unwrap(), Position::from_string("g8").unwrap()), Err(MoveError::GameOver));
+    }
+
+    #[test]
+    fn san_tests() {
+        let mut game = Game::new();
+        let san = |game: &Game, from: &str, to: &str| {
+            game.move_to_san(Position::from_string(from).unwrap(), Position::from_string(to).unwrap())
+        };
+
+        // scenario 1: simple moves
+        assert_eq!(san(&game, "g1", "f3"), Some(String::from("Nf3")));
+        assert_eq!(san(&game, "e2", "e4"), Some(String::from("e4")));
+        assert_eq!(san(&game, "e2", "e5"), None);
+
+        // scenario 2: pawn capture
+        game.load_fen("rnbqkbnr/ppp1pppp/8/3p4/4P3/8/PPPP1PPP/RNBQKBNR w KQkq - 0 2").unwrap();
+        assert_eq!(san(&game, "e4", "d5"), Some(String::from("exd5")));
+
+        // scenario 3: castling
+        game.load_fen("r3k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap();
+        assert_eq!(san(&game, "e1", "g1"), Some(String::from("O-O")));
+
+        // scenario 4: promotion with check
+        game.load_fen("7k/4P3/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(san(&game, "e7", "e8"), Some(String::from("e8=Q+")));
+        assert_eq!(
+            game.move_to_san_with_promotion(Position::from_string("e7").unwrap(), Position::from_string("e8").unwrap(), PieceType::Knight),
+            Some(String::from("e8=N"))
+        );
+
+        // scenario 5: checkmate
+        game.load_fen("rnbqkbnr/pppp1ppp/8/4p3/6P1/5P2/PPPPP2P/RNBQKBNR b KQkq - 0 2").unwrap();
+        assert_eq!(san(&game, "d8", "h4"), Some(String::from("Qh4#")));
+
+        // scenario 6: disambiguation by file, rank and both
+        game.load_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(san(&game, "b1", "d2"), Some(String::from("Nbd2")));
+        game.load_fen("4k3/8/8/R7/8/8/8/R3K3 w - - 0 1").unwrap();
+        assert_eq!(san(&game, "a1", "a3"), Some(String::from("R1a3")));
+        game.load_fen("K7/8/k7/8/4Q2Q/8/8/7Q w - - 0 1").unwrap();
+        assert_eq!(san(&game, "h4", "e1"), Some(String::from("Qh4e1")));
+    }
+
+    #[test]
+    fn make_move_san_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: disambiguation
+        game.load_fen("4k3/8/8/8/8/5N2/8/1N2K3 w - - 0 1").unwrap();
+        assert_eq!(game.make_move_san("Nd2"), Err(SanError::Ambiguous));
+        let made_move = game.make_move_san("Nbd2").unwrap();
+        assert_eq!(made_move.from, Position::from_string("b1").unwrap());
+
+        // scenario 2: castling
+        game.load_fen("r3k3/8/8/8/8/8/8/4K3 b q - 0 1").unwrap();
+        assert!(game.make_move_san("O-O-O").unwrap().is_castle);
+        assert!(game.get_square(Position::from_string("d8").unwrap()).is_some());
+
+        // scenario 3: en passant
+        game.load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        assert!(game.make_move_san("exd6 e.p.").unwrap().is_en_passant);
+
+        // scenario 4: promotion with capture
+        game.load_fen("4k3/8/8/8/8/8/5p2/K5R1 b - - 0 1").unwrap();
+        let made_move = game.make_move_san("fxg1=N").unwrap();
+        assert_eq!(made_move.promotion, Some(PieceType::Knight));
+        assert_eq!(game.move_history().last(), Some(&made_move));
+        assert_eq!(
+            game.get_square(Position::from_string("g1").unwrap()),
+            Some(Piece { piece_type: PieceType::Knight, color: Color::Black })
+        );
+
+        // scenario 5: errors
+        game = Game::new();
+        assert_eq!(game.make_move_san("e5"), Err(SanError::Illegal));
+        assert_eq!(game.make_move_san("O-O"), Err(SanError::Illegal));
+        assert_eq!(game.make_move_san("hello"), Err(SanError::Invalid));
+        assert_eq!(game.make_move_san(""), Err(SanError::Invalid));
+        assert!(game.make_move_san("e4").is_ok());
+        assert!(game.make_move_san("e5").is_ok());
+        assert!(game.make_move_san("Nf3").is_ok());
+    }
+
+    #[test]
+    fn pgn_export_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: no moves
+        assert!(game.to_pgn().ends_with("[Result \"*\"]\n\n*"));
+
+        // scenario 2: fool's mate with tags
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            game.make_move_san(san).unwrap();
+        }
+        let tags = PgnTags {
+            white: String::from("Fool"),
+            ..Default::default()
+        };
+        assert_eq!(
+            game.to_pgn_with_tags(&tags),
+            "[Event \"?\"]\n\
+             [Site \"?\"]\n\
+             [Date \"????.??.??\"]\n\
+             [Round \"?\"]\n\
+             [White \"Fool\"]\n\
+             [Black \"?\"]\n\
+             [Result \"0-1\"]\n\
+             \n\
+             1. f3 e5 2. g4 Qh4# 0-1"
+        );
+
+        // scenario 3: custom starting position with black to move and a promotion
+        game.load_fen("4k3/p7/8/8/8/8/5p2/K5R1 b - - 0 40").unwrap();
+        game.make_move_san("fxg1=N").unwrap();
+        game.make_move_san("Kb2").unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[FEN \"4k3/p7/8/8/8/8/5p2/K5R1 b - - 0 40\"]"));
+        assert!(pgn.ends_with("40... fxg1=N 41. Kb2 *"));
+
+        // scenario 4: the moves are replayed with the game's own rules, here an atomic capture that
+        // only works because the pawn on d5 blew up
+        let mut game = GameBuilder::new().atomic(true).build().unwrap();
+        for san in ["e4", "d5", "exd5", "Qxd2"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert!(game.to_pgn().contains("1. e4 d5 2. exd5 Qxd2"));
+
+        // scenario 5: editing the board mid-game starts the movetext over from the edited position
+        let mut game = Game::new();
+        game.make_move_san("e4").unwrap();
+        game.set_square(Position::from_string("d7").unwrap(), None);
+        game.make_move_san("Qd6").unwrap();
+        let pgn = game.to_pgn();
+        assert!(pgn.contains("[FEN \"rnbqkbnr/ppp1pppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR b KQkq e3 0 1\"]"));
+        assert!(pgn.ends_with("1... Qd6 *"));
+    }
+
+    #[test]
+    fn pgn_import_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: the opera game, with tags, comments, variations and annotations
+        game.load_pgn(
+            "[Event \"Paris\"]\n\
+             [Result \"1-0\"]\n\
+             \n\
+             1. e4 e5 2. Nf3 d6 3. d4 Bg4 {a dubious move} 4. dxe5 Bxf3 5. Qxf3 dxe5 6. Bc4 Nf6\n\
+             7. Qb3 Qe7 8. Nc3 c6 9. Bg5 b5 (9... Qb4+ 10. Qxb4) 10. Nxb5 cxb5 11.Bxb5+ Nbd7\n\
+             12. O-O-O Rd8 13. Rxd7 Rxd7 14. Rd1 Qe6 15. Bxd7+ $1 Nxd7 16. Qb8+! Nxb8 17. Rd8# ; mate\n\
+             1-0",
+        )
+        .unwrap();
+        assert_eq!(game.to_fen(), "1n1Rkb1r/p4ppp/4q3/4p1B1/4P3/8/PPP2PPP/2K5 b k - 1 17");
+        assert_eq!(game.game_state, GameState::Checkmate(Color::Black));
+
+        // scenario 2: custom starting position with a promotion, round-tripped through the exporter
+        game.load_pgn("[FEN \"4k3/p7/8/8/8/8/5p2/K5R1 b - - 0 40\"]\n\n40... fxg1=N 41. Kb2 *").unwrap();
+        assert_eq!(game.to_fen(), "4k3/p7/8/8/8/8/1K6/6n1 b - - 1 41");
+        let fen = game.to_fen();
+        let pgn = game.to_pgn();
+        game.load_pgn(&pgn).unwrap();
+        assert_eq!(game.to_fen(), fen);
+
+        // scenario 3: an illegal move leaves the game untouched
+        assert_eq!(
+            game.load_pgn("1. e4 e5 2. Ke3"),
+            Err(PgnError::BadMove {
+                move_number: 2,
+                san: String::from("Ke3"),
+                error: SanError::Illegal,
+            })
+        );
+        assert_eq!(game.to_fen(), fen);
+
+        // scenario 4: ambiguous move
+        assert_eq!(
+            game.load_pgn("1. Nf3 Nf6 2. Nc3 Nc6 3. Ne4 Ne5 4. Nd4 h6 5. Nc5 a6 6. Ne6"),
+            Err(PgnError::BadMove {
+                move_number: 6,
+                san: String::from("Ne6"),
+                error: SanError::Ambiguous,
+            })
+        );
+
+        // scenario 5: the game's optional rules are kept
+        let mut game = GameBuilder::new().atomic(true).build().unwrap();
+        game.load_pgn("1. e4 d5 2. exd5 Qxd2").unwrap();
+        assert!(game.atomic());
+        assert!(game.get_square(Position::from_string("e1").unwrap()).is_none());
+        assert_eq!(game.get_game_result(), GameResult::BlackWins);
+    }
+
+    #[test]
+    fn game_result_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.get_game_result(), GameResult::Ongoing);
+
+        // scenario 1: black checkmates white
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert_eq!(game.get_game_result(), GameResult::BlackWins);
+
+        // scenario 2: white checkmates black
+        game.load_fen("7k/5N1p/8/8/8/8/8/2K3R1 b - - 0 1").unwrap();
+        assert_eq!(game.get_game_result(), GameResult::WhiteWins);
+
+        // scenario 3: stalemate
+        game.load_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game.get_game_result(), GameResult::Draw);
+
+        // scenario 4: check isn't over yet
+        game.load_fen("4k3/8/8/8/8/8/8/R3K2r w - - 0 1").unwrap();
+        assert_eq!(game.get_game_result(), GameResult::Ongoing);
+    }
+
+    #[test]
+    fn resign_tests() {
+        let mut game = Game::new();
+
+        game.resign(Color::White);
+        assert_eq!(game.game_state, GameState::Resignation(Color::White));
+        assert_eq!(game.get_game_result(), GameResult::BlackWins);
+        assert!(game.make_move(Position::from_string("e2").unwrap(), Position::from_string("e4").unwrap()).is_err());
+        assert_eq!(game.make_move(Position::from_string("e2").unwrap(), Position::from_string("e4").unwrap()), Err(MoveError::GameOver));
+
+        // the game is already over
+        game.resign(Color::Black);
+        assert_eq!(game.get_game_result(), GameResult::BlackWins);
+        assert_eq!(game.offer_draw(Color::White), MoveResult::Disallowed);
+    }
+
+    #[test]
+    fn draw_agreement_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: nothing to accept, and a color can't accept its own offer
+        assert_eq!(game.accept_draw(Color::Black), MoveResult::Disallowed);
+        assert_eq!(game.offer_draw(Color::White), MoveResult::Allowed);
+        assert_eq!(game.accept_draw(Color::White), MoveResult::Disallowed);
+
+        // scenario 2: the offer survives the offering side's move, but moving declines it
+        game.make_move_san("e4").unwrap();
+        assert_eq!(game.draw_offer(), Some(Color::White));
+        game.make_move_san("e5").unwrap();
+        assert_eq!(game.draw_offer(), None);
+        assert_eq!(game.accept_draw(Color::Black), MoveResult::Disallowed);
+
+        // scenario 3: accepted
+        game.offer_draw(Color::White);
+        assert_eq!(game.accept_draw(Color::Black), MoveResult::Allowed);
+        assert_eq!(game.game_state, GameState::Draw);
+        assert_eq!(game.get_game_result(), GameResult::Draw);
+    }
+
+    #[test]
+    fn make_move_promote_tests() {
+        let mut game = Game::from_fen("4k3/1P5p/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        let from = Position::from_string("b7").unwrap();
+        let to = Position::from_string("b8").unwrap();
+
+        // scenario 1: kings and pawns can't be promoted to, and the move isn't made
+        assert_eq!(game.make_move_promote(from, to, PieceType::King), MoveResult::Disallowed);
+        assert_eq!(game.make_move_promote(from, to, PieceType::Pawn), MoveResult::Disallowed);
+        assert_eq!(game.get_square(to), None);
+
+        // scenario 2: the move and promotion happen together
+        assert_eq!(game.make_move_promote(from, to, PieceType::Knight), MoveResult::Allowed);
+        assert_eq!(game.get_square(to), Some(Piece { piece_type: PieceType::Knight, color: Color::White }));
+        assert_eq!(game.game_state, GameState::Normal);
+        assert_eq!(game.move_history().last().unwrap().promotion, Some(PieceType::Knight));
+
+        // scenario 3: moves that don't promote ignore the promotion piece
+        assert_eq!(
+            game.make_move_promote(Position::from_string("e8").unwrap(), Position::from_string("e7").unwrap(), PieceType::Queen),
+            MoveResult::Allowed
+        );
+        assert_eq!(game.turn, Color::White);
+    }
+
+    #[test]
+    fn is_capture_tests() {
+        let game = Game::from_fen("4k3/8/8/2npP3/8/8/8/4K3 w - d6 0 1").unwrap();
+
+        // en passant, quiet move, and a capture that isn't legal for a pawn
+        assert!(game.is_capture(pos("e5"), pos("d6")));
+        assert!(!game.is_capture(pos("e5"), pos("e6")));
+        assert!(!game.is_capture(pos("e5"), pos("d5")));
+
+        // normal capture, and a quiet bishop move
+        let game = Game::from_fen("4k3/8/8/3n4/4B3/8/8/4K3 w - - 0 1").unwrap();
+        assert!(game.is_capture(pos("e4"), pos("d5")));
+        assert!(!game.is_capture(pos("e4"), pos("c6")));
+    }
+
+    #[test]
+    fn simulate_move_tests() {
+        let mut game = Game::new();
+        game.load_fen("4k3/8/8/8/8/8/8/R3K3 w - - 0 1").unwrap();
+        let original_fen = game.to_fen();
+
+        // scenario 1: legal move giving check
+        let simulated = game.simulate_move(Position::from_string("a1").unwrap(), Position::from_string("a8").unwrap());
+        assert!(simulated.is_some());
+        assert_eq!(simulated.unwrap().game_state, GameState::Check(Color::Black));
+        assert_eq!(game.to_fen(), original_fen);
+
+        // scenario 2: illegal move
+        assert!(game.simulate_move(Position::from_string("a1").unwrap(), Position::from_string("b2").unwrap()).is_none());
+        assert_eq!(game.to_fen(), original_fen);
+    }
+
+    #[test]
+    fn threefold_repetition_tests() {
+        let shuffle_knights = |game: &mut Game| {
+            game.make_move(Position::from_string("g1").unwrap(), Position::from_string("f3").unwrap()).unwrap();
+            game.make_move(Position::from_string("g8").unwrap(), Position::from_string("f6").unwrap()).unwrap();
+            game.make_move(Position::from_string("f3").unwrap(), Position::from_string("g1").unwrap()).unwrap();
+            game.make_move(Position::from_string("f6").unwrap(), Position::from_string("g8").unwrap()).unwrap();
+        };
+        let mut game = Game::new();
+
+        // scenario 1: threefold repetition can be claimed
+        for _ in 0..2 {
+            assert_eq!(game.can_claim_draw(), None);
+            shuffle_knights(&mut game);
+        }
+        assert_eq!(game.game_state, GameState::Normal);
+        assert_eq!(game.can_claim_draw(), Some(DrawClaim::ThreefoldRepetition));
+        let mut claimed = game.clone();
+        assert_eq!(claimed.claim_draw(), MoveResult::Allowed);
+        assert_eq!(claimed.game_state, GameState::Repetition);
+        assert_eq!(claimed.get_game_result(), GameResult::Draw);
+        assert_eq!(claimed.claim_draw(), MoveResult::Disallowed);
+
+        // scenario 2: fivefold repetition ends the game by itself
+        for _ in 0..2 {
+            assert_eq!(game.game_state, GameState::Normal);
+            shuffle_knights(&mut game);
+        }
+        assert_eq!(game.game_state, GameState::Repetition);
+        assert_eq!(game.can_claim_draw(), None);
+
+        // scenario 3: with automatic draws threefold repetition is enough
+        let mut game = GameBuilder::new().automatic_draws(true).build().unwrap();
+        assert!(game.automatic_draws());
+        assert!(!Game::new().automatic_draws());
+        for _ in 0..2 {
+            shuffle_knights(&mut game);
+        }
+        assert_eq!(game.game_state, GameState::Repetition);
+
+        // loading a fen resets the history
+        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        assert_eq!(game.game_state, GameState::Normal);
+    }
+
+    #[test]
+    fn fifty_move_claim_tests() {
+        let mut game = Game::from_fen("k7/8/8/8/8/8/6p1/K6R w - - 98 80").unwrap();
+
+        // scenario 1: claimable after fifty moves by each side
+        game.make_move_san("Rh3").unwrap();
+        assert_eq!(game.can_claim_draw(), None);
+        assert_eq!(game.claim_draw(), MoveResult::Disallowed);
+        game.make_move_san("Kb7").unwrap();
+        assert_eq!(game.halfmove_clock(), 100);
+        assert_eq!(game.game_state(), GameState::Normal);
+        assert_eq!(game.can_claim_draw(), Some(DrawClaim::FiftyMoveRule));
+
+        // scenario 2: it can still be played on, until a capture or pawn move resets the clock
+        let mut played_on = game.clone();
+        played_on.make_move_san("Rg3").unwrap();
+        played_on.make_move_san("Ka7").unwrap();
+        played_on.make_move_san("Rxg2").unwrap();
+        assert_eq!(played_on.can_claim_draw(), None);
+
+        assert_eq!(game.claim_draw(), MoveResult::Allowed);
+        assert_eq!(game.game_state(), GameState::FiftyMoveRule);
+        assert_eq!(game.make_move(Position::from_string("h3").unwrap(), Position::from_string("h1").unwrap()), Err(MoveError::GameOver));
+
+        // scenario 3: with automatic draws it ends the game right away
+        let game = GameBuilder::new().automatic_draws(true).starting_fen("k7/8/8/8/8/8/6p1/K6R w - - 100 80").build().unwrap();
+        assert_eq!(game.game_state(), GameState::FiftyMoveRule);
+    }
+
+    #[test]
+    fn game_eq_tests() {
+        let original = Game::new();
+
+        // scenario 1: moving the knights out and back only changes the move counters
+        let mut game = original.clone();
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert_eq!(game.fullmove_number, 3);
+        assert!(game == original);
+
+        // scenario 2: different positions
+        game.make_move_san("e4").unwrap();
+        assert!(game != original);
+        assert!(Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap() != original);
+        assert!(Game::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Qkq - 0 1").unwrap() != original);
+        assert!(Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap() != Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap());
+    }
+
+    #[test]
+    fn setup_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: a small position, the rest of the board is cleared
+        game.setup(&[
+            ("e1", PieceType::King, Color::White),
+            ("a1", PieceType::Rook, Color::White),
+            ("e8", PieceType::King, Color::Black),
+        ])
+        .unwrap();
+        assert_eq!(game.to_fen(), "4k3/8/8/8/8/8/8/R3K3 w - - 0 1");
+        assert_eq!(game.get_square_str("a1"), Some(Some(Piece { piece_type: PieceType::Rook, color: Color::White })));
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+
+        // scenario 2: the game state is updated
+        game.setup(&[
+            ("g1", PieceType::King, Color::White),
+            ("a8", PieceType::Rook, Color::Black),
+            ("f2", PieceType::Pawn, Color::White),
+            ("g2", PieceType::Pawn, Color::White),
+            ("h2", PieceType::Pawn, Color::White),
+            ("e8", PieceType::King, Color::Black),
+        ])
+        .unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+        game.setup(&[
+            ("g1", PieceType::King, Color::White),
+            ("a1", PieceType::Rook, Color::Black),
+            ("f2", PieceType::Pawn, Color::White),
+            ("g2", PieceType::Pawn, Color::White),
+            ("h2", PieceType::Pawn, Color::White),
+            ("e8", PieceType::King, Color::Black),
+        ])
+        .unwrap();
+        assert_eq!(game.game_state(), GameState::Checkmate(Color::White));
+
+        // scenario 3: errors leave the game alone
+        let before = game.to_fen();
+        assert_eq!(
+            game.setup(&[("e1", PieceType::King, Color::White), ("e9", PieceType::King, Color::Black)]),
+            Err(SetupError::BadPosition(PositionParseError::BadRank('9')))
+        );
+        assert_eq!(
+            game.setup(&[("e1", PieceType::King, Color::White), ("e8", PieceType::King, Color::Black), ("d8", PieceType::King, Color::Black)]),
+            Err(SetupError::DuplicateKing(Color::Black))
+        );
+        assert_eq!(game.to_fen(), before);
+    }
+
+    #[test]
+    fn termination_tests() {
+        // scenario 1: ongoing games, including checks and pending promotions
+        let mut game = Game::new();
+        assert_eq!(game.termination(), None);
+        game.load_fen("4k3/8/8/8/8/8/8/4R1K1 b - - 0 1").unwrap();
+        assert_eq!(game.termination(), None);
+        game.load_fen("4k2P/8/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert_eq!(game.termination(), None);
+
+        // scenario 2: checkmate and stalemate
+        game.load_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(game.termination(), Some(Termination::Checkmate(Color::White)));
+        game.load_fen("k7/2Q5/1K6/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(game.termination(), Some(Termination::Stalemate));
+
+        // scenario 3: the different draws
+        game.load_fen("k7/8/8/8/8/8/8/K6R w - - 150 80").unwrap();
+        assert_eq!(game.termination(), Some(Termination::FiftyMove));
+        game.load_fen("k7/8/8/8/8/8/8/K7 w - - 0 1").unwrap();
+        assert_eq!(game.termination(), Some(Termination::InsufficientMaterial));
+        let mut game = GameBuilder::new().automatic_draws(true).build().unwrap();
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8", "Nf3", "Nf6", "Ng1", "Ng8"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert_eq!(game.termination(), Some(Termination::Repetition));
+        let mut game = Game::new();
+        game.offer_draw(Color::White);
+        game.accept_draw(Color::Black);
+        assert_eq!(game.termination(), Some(Termination::Agreement));
+
+        // scenario 4: resignation and variants
+        let mut game = Game::new();
+        game.resign(Color::Black);
+        assert_eq!(game.termination(), Some(Termination::Resignation(Color::Black)));
+        let game = GameBuilder::new().king_of_the_hill(true).starting_fen("k7/8/8/8/4K3/8/8/8 b - - 0 1").build().unwrap();
+        assert_eq!(game.termination(), Some(Termination::KingOfTheHill(Color::White)));
+    }
+
+    #[test]
+    fn fen_piece_placement_tests() {
+        // scenario 1: starting position
+        let mut game = Game::new();
+        assert_eq!(game.fen_piece_placement(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR");
+
+        // scenario 2: it's the first segment of the full fen
+        game.make_move_san("e4").unwrap();
+        assert_eq!(game.fen_piece_placement(), "rnbqkbnr/pppppppp/8/8/4P3/8/PPPP1PPP/RNBQKBNR");
+        assert_eq!(game.to_fen().split(' ').next(), Some(game.fen_piece_placement().as_str()));
+
+        // scenario 3: the side to move, castling rights and clocks don't matter
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 w Q - 0 1").unwrap();
+        let other = Game::from_fen("4k3/8/8/8/8/8/8/R3K3 b - - 12 40").unwrap();
+        assert_eq!(game.fen_piece_placement(), other.fen_piece_placement());
+        assert_eq!(game.fen_piece_placement(), "4k3/8/8/8/8/8/8/R3K3");
+    }
+
+    #[test]
+    fn atomic_tests() {
+        let atomic = |fen: &str| GameBuilder::new().atomic(true).starting_fen(fen).build().unwrap();
+
+        // scenario 1: capturing next to the enemy king blows it up and wins
+        let mut game = atomic("3nk3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        game.legal_moves();
+        assert_eq!(game.to_fen(), "3nk3/8/8/8/8/8/8/3QK3 w - - 0 1");
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+        assert!(game.make_move(Position::from_string("d1").unwrap(), Position::from_string("d8").unwrap()).is_ok());
+        assert_eq!(game.game_state(), GameState::Atomic(Color::White));
+        assert_eq!(game.get_game_result(), GameResult::WhiteWins);
+        assert_eq!(game.piece_count(), 1);
+        assert_eq!(game.move_history()[0].captured, Some(Piece { piece_type: PieceType::Knight, color: Color::Black }));
+        assert_eq!(game.make_move(Position::from_string("e1").unwrap(), Position::from_string("e2").unwrap()), Err(MoveError::GameOver));
+
+        // scenario 2: pawns survive the blast, a rook blown up in its corner takes its castling right along
+        let mut game = atomic("r3k3/pp6/8/8/8/8/8/1R2K3 w q - 0 1");
+        game.make_move_san("Rxb7").unwrap();
+        assert_eq!(game.to_fen(), "4k3/p7/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(game.game_state(), GameState::Normal);
+
+        // scenario 3: en passant blows up around the square the pawn lands on
+        let mut game = atomic("4k3/2n1p3/8/3pP3/8/8/8/4K3 w - d6 0 1");
+        game.make_move_san("exd6").unwrap();
+        assert_eq!(game.to_fen(), "4k3/4p3/8/8/8/8/8/4K3 b - - 0 1");
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+
+        // scenario 4: kings can't capture, and a capture can't blow up the own king
+        let game = atomic("4k3/8/8/8/8/8/3p4/3RK3 w - - 0 1");
+        assert!(!game.get_possible_moves(Position::from_string("e1").unwrap()).contains(&Position::from_string("d2").unwrap()));
+        assert!(!game.get_possible_moves(Position::from_string("d1").unwrap()).contains(&Position::from_string("d2").unwrap()));
+        let game = Game::from_fen("4k3/8/8/8/8/8/3p4/3RK3 w - - 0 1").unwrap();
+        assert!(game.get_possible_moves(Position::from_string("e1").unwrap()).contains(&Position::from_string("d2").unwrap()));
+
+        // scenario 5: touching kings are never in check
+        let game = atomic("8/8/8/8/8/R2k4/3K4/8 b - - 0 1");
+        assert_eq!(game.game_state(), GameState::Normal);
+
+        // scenario 6: a pawn capturing onto the last rank is written without a promotion piece and reads back
+        let mut game = atomic("2r1k3/1P5p/8/8/8/8/7P/4K3 w - - 0 1");
+        let mut with_promotion = game.clone();
+        assert_eq!(game.move_to_san(Position::from_string("b7").unwrap(), Position::from_string("c8").unwrap()), Some(String::from("bxc8")));
+        assert_eq!(game.make_move_san("bxc8").unwrap().promotion, None);
+        assert_eq!(game.game_state(), GameState::Normal);
+        assert_eq!(with_promotion.make_move_san("bxc8=Q"), Err(SanError::Invalid));
+        assert!(with_promotion.move_history().is_empty());
+        let fen = game.to_fen();
+        let mut loaded = atomic("2r1k3/1P5p/8/8/8/8/7P/4K3 w - - 0 1");
+        loaded.load_pgn(&game.to_pgn()).unwrap();
+        assert_eq!(loaded.to_fen(), fen);
+    }
+
+    #[test]
+    fn set_turn_tests() {
+        // scenario 1: black is checkmated, but only once it's black's move
+        let mut game = Game::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 w - - 0 1").unwrap();
+        assert_eq!(game.game_state(), GameState::Normal);
+        game.set_turn(Color::Black);
+        assert_eq!(game.turn(), Color::Black);
+        assert_eq!(game.game_state(), GameState::Checkmate(Color::Black));
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+
+        // scenario 2: setting it back
+        game.set_turn(Color::White);
+        assert_eq!(game.game_state(), GameState::Normal);
+        assert_eq!(game.to_fen(), "R5k1/5ppp/8/8/8/8/8/6K1 w - - 0 1");
+
+        // scenario 3: the en passant pawn is dropped when the turn changes, but not when it stays the same
+        let mut game = Game::from_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        game.set_turn(Color::White);
+        assert_eq!(game.en_passant_target(), Some(Position::from_string("d6").unwrap()));
+        game.set_turn(Color::Black);
+        assert_eq!(game.en_passant_susceptible_pawn(), None);
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+
+        // scenario 4: the move history starts over, so pgn export starts from the changed turn
+        let mut game = Game::new();
+        game.make_move_san("e4").unwrap();
+        game.set_turn(Color::White);
+        assert_eq!(game.ply(), 0);
+        assert_eq!(game.position_history, vec![game.zobrist_hash]);
+        game.make_move_san("d4").unwrap();
+        let fen = game.to_fen();
+        let mut loaded = Game::new();
+        loaded.load_pgn(&game.to_pgn()).unwrap();
+        assert_eq!(loaded.to_fen(), fen);
+    }
+
+    #[test]
+    fn legal_moves_iter_tests() {
+        // scenario 1: the same moves in the same order as legal_moves
+        let game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.legal_moves_iter().collect::<Vec<_>>(), game.legal_moves());
+
+        // scenario 2: stopping at the first capture
+        let mut moves: LegalMoves = game.legal_moves_iter();
+        let capture = moves.find(|(_, to)| game.get_square(*to).is_some());
+        let all_moves = game.legal_moves();
+        let capture_index = all_moves.iter().position(|(_, to)| game.get_square(*to).is_some()).unwrap();
+        assert_eq!(capture, Some(all_moves[capture_index]));
+        assert!(game.is_capture(capture.unwrap().0, capture.unwrap().1));
+        // the rest of the moves are still there to continue with
+        assert_eq!(moves.count(), all_moves.len() - capture_index - 1);
+
+        // scenario 3: pinned pieces and moves into check are filtered out, like get_possible_moves
+        let game = Game::from_fen("4k3/4r3/8/8/8/8/4B3/4K3 w - - 0 1").unwrap();
+        assert!(game.legal_moves_iter().all(|(from, _)| from != Position::from_string("e2").unwrap()));
+        assert_eq!(game.legal_moves_iter().count(), 4);
+        let game = Game::from_fen("4k3/8/8/8/8/8/P7/4K3 b - - 0 1").unwrap();
+        assert!(game.legal_moves_iter().all(|(from, _)| from == Position::from_string("e8").unwrap()));
+        assert_eq!(game.legal_moves_iter().count(), 5);
+
+        // scenario 4: nothing once the game is over or while waiting for a promotion, like legal_moves_count
+        let mut game = Game::new();
+        game.resign(Color::Black);
+        assert_eq!(game.legal_moves_iter().next(), None);
+        let mut game = Game::from_fen("4k3/1P5p/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move(Position::from_string("b7").unwrap(), Position::from_string("b8").unwrap()).unwrap();
+        assert_eq!(game.legal_moves_iter().count(), game.legal_moves_count());
+        assert_eq!(game.legal_moves_iter().count(), 0);
+    }
+
+    #[test]
+    fn mirror_vertical_tests() {
+        // scenario 1: black delivering checkmate becomes white delivering checkmate
+        let game = Game::from_fen("rnb1kbnr/pppp1ppp/8/4p3/6Pq/5P2/PPPPP2P/RNBQKBNR w KQkq - 1 3").unwrap();
+        assert_eq!(game.game_state(), GameState::Checkmate(Color::White));
+        assert_eq!(game.get_game_result(), GameResult::BlackWins);
+        let mirrored = game.mirror_vertical();
+        assert_eq!(mirrored.to_fen(), "rnbqkbnr/ppppp2p/5p2/6pQ/4P3/8/PPPP1PPP/RNB1KBNR b KQkq - 1 3");
+        assert_eq!(mirrored.game_state(), GameState::Checkmate(Color::Black));
+        assert_eq!(mirrored.get_game_result(), GameResult::WhiteWins);
+
+        // scenario 2: castling rights and en passant are mirrored, mirroring twice gives the same position back
+        let game = Game::from_fen("r3k2r/8/8/8/3pP3/8/8/4K2R b Kq e3 0 1").unwrap();
+        let mirrored = game.mirror_vertical();
+        assert_eq!(mirrored.to_fen(), "4k2r/8/8/3Pp3/8/8/8/R3K2R w Qk e6 0 1");
+        assert_eq!(mirrored.legal_moves_count(), game.legal_moves_count());
+        assert!(mirrored.mirror_vertical() == game);
+
+        // scenario 3: the optional rules are kept
+        let game = GameBuilder::new().three_check(true).build().unwrap();
+        assert!(game.mirror_vertical().three_check);
+    }
+
+    #[test]
+    fn occupancy_tests() {
+        // scenario 1: the bitboards follow the squares through random games (captures, castling, en passant, promotion)
+        play_random_games(20, 150, |game| {
+            assert_eq!(game.occupancy, Occupancy::from_squares(&game.squares));
+        });
+
+        // scenario 2: loading, clearing and editing the board
+        let mut game = Game::from_fen("r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1").unwrap();
+        assert_eq!(game.occupancy, Occupancy::from_squares(&game.squares));
+        assert_eq!(game.occupancy.all.count_ones(), 32);
+        game.set_piece(Position::from_string("e4").unwrap(), PieceType::Queen, Color::Black).unwrap();
+        game.remove_piece(Position::from_string("a1").unwrap());
+        assert_eq!(game.occupancy, Occupancy::from_squares(&game.squares));
+        game.clear_board();
+        assert_eq!(game.occupancy, Occupancy::default());
+    }
+
+    #[test]
+    fn zobrist_hash_tests() {
+        let mut game = Game::new();
+        let starting_hash = game.zobrist_hash();
+        assert_eq!(starting_hash, game.compute_zobrist_hash());
+
+        // scenario 1: the incremental hash follows captures, castling, en passant and promotion
+        for san in ["e4", "d5", "exd5", "c5", "dxc6", "Nf6", "cxb7", "e6", "bxa8=Q", "Bc5", "Nf3", "O-O", "Bc4", "Nc6", "O-O"] {
+            game.make_move_san(san).unwrap();
+            assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash(), "{}", san);
+        }
+
+        // looking for legal moves makes and takes back moves, which shouldn't change it
+        let hash = game.zobrist_hash();
+        game.legal_moves();
+        assert_eq!(game.zobrist_hash(), hash);
+
+        // scenario 2: transpositions have the same hash, the side to move matters
+        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").unwrap();
+        for san in ["Nf3", "Nf6", "Ng1", "Ng8"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert_eq!(game.zobrist_hash(), starting_hash);
+        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR b KQkq - 0 1").unwrap();
+        assert_ne!(game.zobrist_hash(), starting_hash);
+
+        // scenario 3: castling rights and en passant matter
+        game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w Kkq - 0 1").unwrap();
+        assert_ne!(game.zobrist_hash(), starting_hash);
+        game.load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        let en_passant_hash = game.zobrist_hash();
+        game.load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_ne!(game.zobrist_hash(), en_passant_hash);
+
+        // scenario 4: set_square keeps it up to date
+        game.set_square(Position::from_string("a1").unwrap(), Some(Piece { piece_type: PieceType::Rook, color: Color::White }));
+        assert_eq!(game.zobrist_hash(), game.compute_zobrist_hash());
+    }
+
+    #[test]
+    fn display_tests() {
+        let mut game = Game::new();
+
+        // scenario 1: starting position
+        assert_eq!(
+            game.to_string(),
+            "8 r n b q k b n r\n\
+             7 p p p p p p p p\n\
+             6 . . . . . . . .\n\
+             5 . . . . . . . .\n\
+             4 . . . . . . . .\n\
+             3 . . . . . . . .\n\
+             2 P P P P P P P P\n\
+             1 R N B Q K B N R\n  \
+             a b c d e f g h\n\
+             White to move, Normal"
+        );
+
+        // scenario 2: check
+        game.load_fen("4k3/8/8/8/8/8/8/R3K2r w - - 0 1").unwrap();
+        assert!(game.to_string().ends_with("1 R . . . K . . r\n  a b c d e f g h\nWhite to move, Check(White)"));
+    }
+
+    #[test]
+    fn unicode_board_tests() {
+        let mut game = Game::new();
+        game.load_fen("4k3/8/8/8/8/8/3P4/4K2R w K - 0 1").unwrap();
+
+        assert_eq!(
+            game.to_unicode_board(),
+            "8 · · · · ♚ · · ·\n\
+             7 · · · · · · · ·\n\
+             6 · · · · · · · ·\n\
+             5 · · · · · · · ·\n\
+             4 · · · · · · · ·\n\
+             3 · · · · · · · ·\n\
+             2 · · · ♙ · · · ·\n\
+             1 · · · · ♔ · · ♖\n  \
+             a b c d e f g h"
+        );
+
+        assert_eq!(
+            game.to_unicode_board_flipped(),
+            "1 ♖ · · ♔ · · · ·\n\
+             2 · · · · ♙ · · ·\n\
+             3 · · · · · · · ·\n\
+             4 · · · · · · · ·\n\
+             5 · · · · · · · ·\n\
+             6 · · · · · · · ·\n\
+             7 · · · · · · · ·\n\
+             8 · · · ♚ · · · ·\n  \
+             h g f e d c b a"
+        );
+    }
+
+    #[test]
+    fn oriented_board_tests() {
+        let game = Game::new();
+
+        // scenario 1: start position from both sides
+        assert_eq!(
+            game.to_ascii_oriented(Orientation::White),
+            "8 r n b q k b n r\n\
+             7 p p p p p p p p\n\
+             6 . . . . . . . .\n\
+             5 . . . . . . . .\n\
+             4 . . . . . . . .\n\
+             3 . . . . . . . .\n\
+             2 P P P P P P P P\n\
+             1 R N B Q K B N R\n  \
+             a b c d e f g h"
+        );
+        assert_eq!(
+            game.to_ascii_oriented(Orientation::Black),
+            "1 R N B K Q B N R\n\
+             2 P P P P P P P P\n\
+             3 . . . . . . . .\n\
+             4 . . . . . . . .\n\
+             5 . . . . . . . .\n\
+             6 . . . . . . . .\n\
+             7 p p p p p p p p\n\
+             8 r n b k q b n r\n  \
+             h g f e d c b a"
+        );
+
+        // scenario 2: the other renderers agree
+        assert!(game.to_string().starts_with(&game.to_ascii_oriented(Orientation::White)));
+        assert_eq!(game.to_unicode_board_oriented(Orientation::Black), game.to_unicode_board_flipped());
+        assert_eq!(game.to_unicode_board_oriented(Orientation::White), game.to_unicode_board());
+    }
+
+    #[test]
+    fn describe_last_move_tests() {
+        let mut game = Game::new();
+        assert_eq!(game.describe_last_move(), None);
+
+        // scenario 1: quiet move and capture with check
+        game.make_move_san("e4").unwrap();
+        assert_eq!(game.describe_last_move().unwrap(), "White pawn moves to e4.");
+        game.load_fen("4k3/7p/5n2/8/4N3/8/8/4K3 w - - 0 1").unwrap();
+        game.make_move_san("Nxf6+").unwrap();
+        assert_eq!(game.describe_last_move().unwrap(), "White knight captures on f6, check.");
+
+        // scenario 2: castling
+        game.load_fen("r3k3/8/8/8/8/8/8/4K2R b Kq - 0 1").unwrap();
+        game.make_move_san("O-O-O").unwrap();
+        assert_eq!(game.describe_last_move().unwrap(), "Black castles queenside.");
+        game.make_move_san("O-O").unwrap();
+        assert_eq!(game.describe_last_move().unwrap(), "White castles kingside.");
+
+        // scenario 3: checkmate
+        let mut game = Game::new();
+        for san in ["f3", "e5", "g4", "Qh4#"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert_eq!(game.describe_last_move().unwrap(), "Black queen moves to h4, checkmate.");
+
+        // scenario 4: promotion and en passant
+        game.load_fen("4k3/8/8/8/8/8/5p2/4K1R1 b - - 0 1").unwrap();
+        game.make_move_san("fxg1=N").unwrap();
+        assert_eq!(game.describe_last_move().unwrap(), "Black pawn captures on g1 and promotes to knight.");
+        game.load_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1").unwrap();
+        game.make_move_san("exd6").unwrap();
+        assert_eq!(game.describe_last_move().unwrap(), "White pawn captures en passant on d6.");
+
+        // scenario 5: an atomic capture blows up the capturing piece
+        let mut game = GameBuilder::new().atomic(true).build().unwrap();
+        for san in ["e4", "d5", "exd5"] {
+            game.make_move_san(san).unwrap();
+        }
+        assert!(game.get_square(Position::from_string("d5").unwrap()).is_none());
+        assert_eq!(game.describe_last_move().unwrap(), "White pawn captures on d5.");
+    }
+
+    #[test]
+    fn fen_error_tests() {
+        let mut game = Game::new();
+        let starting_fen = game.to_fen();
+
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/8 w - -"), Err(FenError::WrongSegmentCount));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongRankCount));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/7x w - - 0 1"), Err(FenError::BadPieceChar('x')));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/7 w - - 0 1"), Err(FenError::RankNotEightSquares));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/8k w - - 0 1"), Err(FenError::RankNotEightSquares));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/8 x - - 0 1"), Err(FenError::BadActiveColor));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/8 wb - - 0 1"), Err(FenError::BadActiveColor));
+        assert_eq!(game.load_fen("8/8/8/8/8/8/8/8 w - e5 0 1"), Err(FenError::BadEnPassantSquare));
+        assert_eq!(game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkqZ - 0 1"), Err(FenError::BadCastling));
+        assert_eq!(game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KKq - 0 1"), Err(FenError::BadCastling));
+        assert_eq!(game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x y"), Err(FenError::BadClock));
+        assert_eq!(game.load_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 -1"), Err(FenError::BadClock));
+        assert_eq!(game.load_fen_lenient("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - x"), Err(FenError::BadClock));
+
+        // the board should be left untouched by the failed loads
+        assert_eq!(game.to_fen(), starting_fen);
+    }
+
+    #[test]
+    fn default_tests() {
+        assert!(Game::default() == Game::new());
+        assert_eq!(Game::default().to_fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1");
+    }
+
+    #[test]
+    fn validate_fen_tests() {
+        assert_eq!(validate_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1"), Ok(()));
+        assert_eq!(validate_fen("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1"), Ok(()));
+
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/8 w - -"), Err(FenError::WrongSegmentCount));
+        assert_eq!(validate_fen("8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongRankCount));
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/7x w - - 0 1"), Err(FenError::BadPieceChar('x')));
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/9 w - - 0 1"), Err(FenError::RankNotEightSquares));
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/8 x - - 0 1"), Err(FenError::BadActiveColor));
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/8 w - e5 0 1"), Err(FenError::BadEnPassantSquare));
+
+        // exactly one king per color, which load_fen doesn't insist on
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongKingCount(Color::White)));
+        assert_eq!(validate_fen("4k3/8/8/8/8/8/8/8 w - - 0 1"), Err(FenError::WrongKingCount(Color::White)));
+        assert_eq!(validate_fen("4k3/8/8/8/8/8/8/K3K3 w - - 0 1"), Err(FenError::WrongKingCount(Color::White)));
+        assert_eq!(validate_fen("8/8/8/8/8/8/8/4K3 b - - 0 1"), Err(FenError::WrongKingCount(Color::Black)));
+        assert_eq!(validate_fen("k3k3/8/8/8/8/8/8/4K3 w - - 0 1"), Err(FenError::WrongKingCount(Color::Black)));
+        assert!(Game::new().load_fen("8/8/8/8/8/8/8/8 w - - 0 1").is_ok());
+    }
+
+    #[test]
+    fn from_fen_tests() {
+        let fen = "4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 1";
+        let game = Game::from_fen(fen).unwrap();
+        assert_eq!(game.to_fen(), fen);
+        assert_eq!(game.get_square(Position::from_string("d5").unwrap()), Some(Piece { piece_type: PieceType::Pawn, color: Color::Black }));
+
+        assert_eq!(Game::from_fen("8/8/8/8/8/8/8/8 x - - 0 1").err(), Some(FenError::BadActiveColor));
+    }
+
+    // Parses a square in algebraic notation, e.g. "e4"
+    fn pos(square: &str) -> Position {
+        Position::from_string(square).unwrap()
+    }
+
+    // Plays random games from the starting position, calling check after every move (and its promotion, to a random
+    // piece). A simple xorshift picks the moves, so the games are the same on every run.
+    fn play_random_games(games: usize, max_moves: usize, mut check: impl FnMut(&Game)) {
+        let mut seed: u64 = 0x2545_f491_4f6c_dd1d;
+        let mut random = move |n: usize| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % n as u64) as usize
+        };
+
+        for _ in 0..games {
+            let mut game = Game::new();
+            for _ in 0..max_moves {
+                let moves = game.legal_moves();
+                if moves.is_empty() {
+                    break;
+                }
+                let (from, to) = moves[random(moves.len())];
+                let promotion = [PieceType::Queen, PieceType::Rook, PieceType::Bishop, PieceType::Knight][random(4)];
+                assert_eq!(game.make_move_promote(from, to, promotion), MoveResult::Allowed);
+                check(&game);
+            }
+        }
     }
 
     // Checks if two vectors contain the exact same elements (order doesn't matter)
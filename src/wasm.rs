@@ -0,0 +1,54 @@
+use wasm_bindgen::prelude::*;
+
+use crate::{Game, GameState, MoveResult, PieceType, Position};
+
+// A game behind the wasm boundary, where positions are passed as algebraic notation (e.g. "e4")
+#[wasm_bindgen]
+pub struct WasmGame {
+    game: Game,
+}
+
+#[wasm_bindgen]
+pub fn new_game() -> WasmGame {
+    WasmGame { game: Game::new() }
+}
+
+#[wasm_bindgen]
+impl WasmGame {
+    // Whether the move was made, unparseable positions aren't
+    pub fn make_move(&mut self, from: &str, to: &str) -> bool {
+        match (from.parse::<Position>(), to.parse::<Position>()) {
+            (Ok(from), Ok(to)) => self.game.make_move(from, to).is_ok(),
+            _ => false,
+        }
+    }
+
+    // Whether the pawn waiting for promotion was promoted to "q", "r", "b" or "n"
+    pub fn promote(&mut self, piece: &str) -> bool {
+        let mut chars = piece.chars();
+        match (chars.next().and_then(PieceType::from_fen_char), chars.next()) {
+            (Some(piece_type), None) => self.game.promote(piece_type) == MoveResult::Allowed,
+            _ => false,
+        }
+    }
+
+    // Destinations of the piece at from, empty if the position can't be parsed
+    pub fn get_possible_moves(&self, from: &str) -> Vec<String> {
+        match from.parse::<Position>() {
+            Ok(from) => self.game.get_possible_moves(from).iter().map(|to| to.to_string()).collect(),
+            Err(_) => Vec::new(),
+        }
+    }
+
+    pub fn to_fen(&self) -> String {
+        self.game.to_fen()
+    }
+
+    // The game state as it's written in rust, e.g. "Check(White)", with positions in algebraic notation
+    pub fn game_state_string(&self) -> String {
+        match self.game.game_state() {
+            GameState::AwaitingPromotion(pos) => format!("AwaitingPromotion({})", pos),
+            game_state => format!("{:?}", game_state),
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use crate::{Color, Game, Piece, PieceType, Position};
+
+// One key per piece type and color on every square, then side to move, the four castling rights and the en passant files
+const PIECE_KEYS: usize = 12 * 64;
+const SIDE_KEY: usize = PIECE_KEYS;
+const CASTLING_KEYS: usize = SIDE_KEY + 1;
+const EN_PASSANT_KEYS: usize = CASTLING_KEYS + 4;
+const KEY_COUNT: usize = EN_PASSANT_KEYS + 8;
+
+// Fixed pseudo random keys (splitmix64 from a fixed seed), so hashes are the same on every run
+const KEYS: [u64; KEY_COUNT] = generate_keys();
+
+const fn generate_keys() -> [u64; KEY_COUNT] {
+    let mut keys = [0; KEY_COUNT];
+    let mut state: u64 = 0x0123_4567_89ab_cdef;
+    let mut i = 0;
+    while i < KEY_COUNT {
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        keys[i] = z ^ (z >> 31);
+        i += 1;
+    }
+    keys
+}
+
+pub(crate) fn piece_key(piece: Piece, position: Position) -> u64 {
+    let piece_index = match piece.piece_type {
+        PieceType::Pawn => 0,
+        PieceType::Knight => 1,
+        PieceType::Bishop => 2,
+        PieceType::Rook => 3,
+        PieceType::Queen => 4,
+        PieceType::King => 5,
+    } + if piece.color == Color::White { 0 } else { 6 };
+
+    KEYS[piece_index * 64 + position.y as usize * 8 + position.x as usize]
+}
+
+impl Game {
+    // Zobrist hash of the position, kept up to date as moves are made
+    pub fn zobrist_hash(&self) -> u64 {
+        self.zobrist_hash
+    }
+
+    // Computes the zobrist hash from scratch
+    pub fn compute_zobrist_hash(&self) -> u64 {
+        let mut hash = self.state_key();
+        for x in 0..=7 {
+            for y in 0..=7 {
+                let pos = Position::new(x, y);
+                if let Some(piece) = self.get_square(pos) {
+                    hash ^= piece_key(piece, pos);
+                }
+            }
+        }
+        hash
+    }
+
+    // The part of the hash that doesn't depend on the board: side to move, castling rights and en passant file
+    pub(crate) fn state_key(&self) -> u64 {
+        let mut key = 0;
+        if self.turn == Color::Black {
+            key ^= KEYS[SIDE_KEY];
+        }
+
+        let castling_available = [
+            self.white_castling_kingside_available,
+            self.white_castling_queenside_available,
+            self.black_castling_kingside_available,
+            self.black_castling_queenside_available,
+        ];
+        for (i, available) in castling_available.iter().enumerate() {
+            if *available {
+                key ^= KEYS[CASTLING_KEYS + i];
+            }
+        }
+
+        if let Some(pawn) = self.en_passant_susceptible_pawn {
+            key ^= KEYS[EN_PASSANT_KEYS + pawn.x as usize];
+        }
+        key
+    }
+}